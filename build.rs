@@ -1,5 +1,8 @@
-use anyhow::Result;
-use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+
+/// The client crates hydrated in the browser, built for wasm before sunfish bundles them.
+const CLIENT_CRATES: &[&str] = &["deciduously_com_sunfish_blog_client"];
 
 fn main() -> Result<()> {
 	let crate_path = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap());
@@ -7,6 +10,14 @@ fn main() -> Result<()> {
 	let crate_out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
 	let css_paths = vec![workspace_path.clone()];
 	println!("cargo:rerun-if-changed=.");
+	for client_crate in CLIENT_CRATES {
+		let wasm_path = build_client(client_crate, &crate_out_dir)?;
+		// sunfish expects the wasm of each client crate from an artifact dependency, which needs a nightly cargo, so provide it the same way.
+		std::env::set_var(
+			format!("CARGO_BIN_FILE_{}", client_crate.to_uppercase()),
+			wasm_path,
+		);
+	}
 	sunfish::build(sunfish::BuildOptions {
 		workspace_path,
 		crate_path,
@@ -15,3 +26,30 @@ fn main() -> Result<()> {
 	})?;
 	Ok(())
 }
+
+/// Build a client crate for wasm in its own target directory, so it does not wait on the lock held by the build running this script.
+fn build_client(client_crate: &str, crate_out_dir: &Path) -> Result<PathBuf> {
+	let cargo = std::env::var_os("CARGO").unwrap();
+	let profile = std::env::var("PROFILE").unwrap();
+	let target_dir = crate_out_dir.join("client");
+	let mut command = std::process::Command::new(cargo);
+	command
+		.args(["build", "--package", client_crate])
+		.args(["--target", "wasm32-unknown-unknown"])
+		.arg("--target-dir")
+		.arg(&target_dir)
+		.env_remove("CARGO_ENCODED_RUSTFLAGS")
+		.env_remove("RUSTC_WORKSPACE_WRAPPER")
+		.env_remove("RUSTFLAGS");
+	if profile == "release" {
+		command.arg("--release");
+	}
+	let status = command.status()?;
+	if !status.success() {
+		return Err(anyhow!("failed to build {client_crate}"));
+	}
+	Ok(target_dir
+		.join("wasm32-unknown-unknown")
+		.join(profile)
+		.join(format!("{client_crate}.wasm")))
+}
@@ -0,0 +1,117 @@
+//! Compile the Rust snippets embedded in blog posts to catch examples that no longer build.
+use anyhow::{anyhow, Result};
+use deciduously_com_sunfish_content::{BlogPost, Content};
+use deciduously_com_sunfish_ui as ui;
+use std::{
+	fmt::Write,
+	path::{Path, PathBuf},
+	process::Command,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+	#[clap(help = "Only check the posts with these slugs")]
+	slugs: Vec<String>,
+	#[clap(
+		long,
+		help = "Check every Rust code block, not only the ones marked check"
+	)]
+	all: bool,
+	#[clap(
+		env,
+		long,
+		help = "The rustc to compile snippets with",
+		default_value = "rustc"
+	)]
+	rustc: String,
+	#[clap(
+		long = "extern",
+		value_name = "NAME=PATH",
+		help = "A crate snippets may use, passed to rustc as --extern"
+	)]
+	externs: Vec<String>,
+	#[clap(
+		short = 'L',
+		value_name = "PATH",
+		help = "A directory to search for the dependencies of --extern crates"
+	)]
+	library_paths: Vec<PathBuf>,
+}
+
+/// Compile the `rust` code blocks marked `check`, or every one with `--all`, like doctests and again as a test harness, honoring the `ignore`, `no_run` and `compile_fail` attributes, and report the ones that fail by slug and line. Posts are read with their includes expanded, so the code they include is checked too, and lines after an include are counted in the expanded post.
+pub fn check_snippets(args: &Args) -> Result<()> {
+	let out_dir = std::env::temp_dir().join("deciduously_com_check_snippets");
+	std::fs::create_dir_all(&out_dir)?;
+	let mut slugs = BlogPost::slugs()?;
+	if !args.slugs.is_empty() {
+		slugs.retain(|slug| args.slugs.contains(slug));
+	}
+	slugs.sort();
+	let mut checked = 0;
+	let mut failures = 0;
+	for slug in slugs {
+		let post_path = Path::new(&slug).join("post.md");
+		let post = BlogPost::source(&slug)?;
+		for code_block in ui::Markdown::new(post).code_blocks() {
+			let info = &code_block.info;
+			if info.language.as_deref() != Some("rust")
+				|| info.has_flag("ignore")
+				|| !(args.all || info.has_flag("check"))
+			{
+				continue;
+			}
+			checked += 1;
+			let source = ui::doctest_source(&ui::all_rust_lines(&code_block.code));
+			let source_path = out_dir.join(format!("{slug}_{}.rs", code_block.line));
+			std::fs::write(&source_path, source)?;
+			// Snippets are compiled as a test harness too, so the code under `#[test]` and `#[cfg(test)]` is checked as well.
+			let mut output = None;
+			for crate_type in [["--crate-type", "bin"], ["--test", "--crate-type=bin"]] {
+				let mut command = Command::new(&args.rustc);
+				command
+					.arg("--edition")
+					.arg(info.edition())
+					.args(crate_type)
+					.args(["--crate-name", "snippet"])
+					.args([
+						"--emit=metadata",
+						"--error-format=short",
+						"--cap-lints=allow",
+					])
+					.arg("--out-dir")
+					.arg(&out_dir);
+				for name_and_path in &args.externs {
+					command.arg("--extern").arg(name_and_path);
+				}
+				for library_path in &args.library_paths {
+					command.arg("-L").arg(library_path);
+				}
+				let crate_output = command.arg(&source_path).output()?;
+				let success = crate_output.status.success();
+				output = Some(crate_output);
+				if !success {
+					break;
+				}
+			}
+			let output = output.unwrap();
+			let compile_fail = info.has_flag("compile_fail");
+			let message = match (output.status.success(), compile_fail) {
+				(true, true) => "compiled, but is marked compile_fail",
+				(false, false) => "failed to compile",
+				_ => continue,
+			};
+			failures += 1;
+			let mut report = format!("{}:{}: {message}", post_path.display(), code_block.line);
+			for line in String::from_utf8_lossy(&output.stderr).lines() {
+				let _ = write!(&mut report, "\n    {line}");
+			}
+			println!("{report}");
+		}
+	}
+	println!("checked {checked} snippets, {failures} failed");
+	if failures > 0 {
+		Err(anyhow!("{failures} snippets failed"))
+	} else {
+		Ok(())
+	}
+}
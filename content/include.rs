@@ -0,0 +1,127 @@
+//! Pull code blocks into posts from source files kept next to them.
+use anyhow::{anyhow, Result};
+use std::path::{Component, Path};
+
+/// Expand `{{#include path}}` directives on their own line into fenced code blocks, with the language inferred from the file extension. As in mdBook, `path:start:end` selects a range of lines, where either end may be left out and `path:line` selects one line, and `path:name` selects the lines between `ANCHOR: name` and `ANCHOR_END: name` comments.
+pub fn expand_includes(markdown: &str, read: impl Fn(&Path) -> Option<String>) -> Result<String> {
+	let mut expanded = String::new();
+	// The character and length of the fence of the code block being skipped, if any.
+	let mut fence: Option<(char, usize)> = None;
+	for line in markdown.lines() {
+		let trimmed = line.trim();
+		if let Some((marker, len)) = fence {
+			if trimmed.len() >= len && trimmed.chars().all(|c| c == marker) {
+				fence = None;
+			}
+		} else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+			let marker = trimmed.chars().next().unwrap();
+			let len = trimmed.chars().take_while(|c| *c == marker).count();
+			fence = Some((marker, len));
+		} else if let Some(directive) = trimmed
+			.strip_prefix("{{#include ")
+			.and_then(|directive| directive.strip_suffix("}}"))
+		{
+			expanded.push_str(&include(directive.trim(), &read)?);
+			continue;
+		}
+		expanded.push_str(line);
+		expanded.push('\n');
+	}
+	Ok(expanded)
+}
+
+fn include(directive: &str, read: &impl Fn(&Path) -> Option<String>) -> Result<String> {
+	let (path, selector) = directive
+		.split_once(':')
+		.map_or((directive, None), |(path, selector)| (path, Some(selector)));
+	let path = Path::new(path);
+	if !path
+		.components()
+		.all(|component| matches!(component, Component::Normal(_)))
+	{
+		return Err(anyhow!(
+			"included files must be inside the post's directory: {}",
+			path.display()
+		));
+	}
+	let file = read(path).ok_or_else(|| anyhow!("no file to include at {}", path.display()))?;
+	let lines = file.lines().collect::<Vec<_>>();
+	let selected = match selector {
+		None => lines,
+		Some(selector) => {
+			select(&lines, selector).ok_or_else(|| anyhow!("nothing to include for {directive}"))?
+		}
+	};
+	let code = selected
+		.into_iter()
+		.filter(|line| !line.contains("ANCHOR:") && !line.contains("ANCHOR_END:"))
+		.collect::<Vec<_>>()
+		.join("\n");
+	// The fence must be longer than any run of backticks in the code it wraps.
+	let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+	let fence = "`".repeat(longest_run.max(2) + 1);
+	let language = path
+		.extension()
+		.and_then(|extension| extension.to_str())
+		.map_or("", language_for_extension);
+	Ok(format!(
+		"{fence}{language},title=\"{}\"\n{code}\n{fence}\n",
+		path.display()
+	))
+}
+
+fn select<'a>(lines: &[&'a str], selector: &str) -> Option<Vec<&'a str>> {
+	let range = |start: &str, end: &str| -> Option<Vec<&'a str>> {
+		let start: usize = if start.is_empty() {
+			1
+		} else {
+			start.parse().ok()?
+		};
+		let end: usize = if end.is_empty() {
+			lines.len()
+		} else {
+			end.parse().ok()?
+		};
+		lines
+			.get(start.checked_sub(1)?..end.min(lines.len()))
+			.map(<[_]>::to_vec)
+	};
+	if let Some((start, end)) = selector.split_once(':') {
+		range(start, end)
+	} else if selector.chars().all(|c| c.is_ascii_digit()) {
+		range(selector, selector)
+	} else {
+		let start = lines
+			.iter()
+			.position(|line| anchor(line, "ANCHOR:") == Some(selector))?;
+		let len = lines[start + 1..]
+			.iter()
+			.position(|line| anchor(line, "ANCHOR_END:") == Some(selector))?;
+		Some(lines[start + 1..start + 1 + len].to_vec())
+	}
+}
+
+fn anchor<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+	let (_, name) = line.split_once(marker)?;
+	name.split_whitespace().next()
+}
+
+fn language_for_extension(extension: &str) -> &str {
+	match extension {
+		"c" | "h" => "c",
+		"clj" | "cljs" => "clojure",
+		"cc" | "cpp" | "hpp" => "cpp",
+		"el" => "elisp",
+		"ex" | "exs" => "elixir",
+		"hs" => "haskell",
+		"js" => "javascript",
+		"ml" => "ocaml",
+		"py" => "python",
+		"rb" => "ruby",
+		"rs" => "rust",
+		"sh" => "bash",
+		"ts" => "typescript",
+		"yml" => "yaml",
+		extension => extension,
+	}
+}
@@ -1,9 +1,16 @@
 use anyhow::{anyhow, Result};
 use deciduously_com_sunfish_ui as ui;
-use std::path::{Path, PathBuf};
+use std::{
+	borrow::Cow,
+	path::{Path, PathBuf},
+};
 use sunfish::{include_dir, include_dir::IncludeDir};
 use url::Url;
 
+pub use self::include::expand_includes;
+
+mod include;
+
 pub struct BlogPost;
 
 #[derive(serde::Deserialize, PartialEq, Eq)]
@@ -21,6 +28,60 @@ impl Content for BlogPost {
 	fn content() -> IncludeDir {
 		include_dir!("content/blog")
 	}
+
+	fn typography() -> ui::Typography {
+		ui::Typography {
+			smart_punctuation: true,
+			emoji_shortcodes: true,
+		}
+	}
+
+	fn code_fold_lines() -> Option<usize> {
+		Some(40)
+	}
+}
+
+/// A post as it is listed on the blog index.
+pub struct BlogIndexEntry {
+	pub slug: String,
+	pub front_matter: BlogPostFrontMatter,
+	/// The excerpt, rendered together with the other posts' so the ids of their code blocks are unique on the page.
+	pub excerpt: ui::RenderedMarkdown,
+}
+
+static BLOG_INDEX: once_cell::sync::OnceCell<Vec<BlogIndexEntry>> =
+	once_cell::sync::OnceCell::new();
+
+impl BlogPost {
+	/// Every post, newest first, with its excerpt rendered. The posts never change while the site runs, so they are read and rendered once, which happens at startup.
+	pub fn index() -> Result<&'static [BlogIndexEntry]> {
+		let index = BLOG_INDEX.get_or_try_init(|| {
+			let blog_posts = BlogPost::list()?;
+			let excerpts = blog_posts
+				.iter()
+				.map(|blog_post| blog_post.excerpt.clone())
+				.collect::<Vec<_>>();
+			let excerpts = ui::Markdown::render_all(&excerpts);
+			let index = blog_posts
+				.into_iter()
+				.zip(excerpts)
+				.map(|(blog_post, excerpt)| BlogIndexEntry {
+					slug: blog_post.slug,
+					front_matter: blog_post.front_matter,
+					excerpt,
+				})
+				.collect();
+			anyhow::Ok(index)
+		})?;
+		Ok(index)
+	}
+}
+
+/// The typography options any content's front matter may set.
+#[derive(serde::Deserialize)]
+struct TypographyFrontMatter {
+	smart_punctuation: Option<bool>,
+	emoji_shortcodes: Option<bool>,
 }
 
 impl PartialOrd for BlogPostFrontMatter {
@@ -41,8 +102,26 @@ pub struct ContentItem<T: Ord> {
 	pub slug: String,
 	pub front_matter: T,
 	pub markdown: ui::Markdown,
+	/// The start of the post, to show in lists of posts.
+	pub excerpt: ui::Markdown,
+	/// The plain text of the excerpt, cut short enough for a meta description.
+	pub summary: String,
+}
+
+impl ContentItem<BlogPostFrontMatter> {
+	/// The description from the front matter, or else the summary.
+	#[must_use]
+	pub fn description(&self) -> &str {
+		self.front_matter
+			.description
+			.as_deref()
+			.unwrap_or(&self.summary)
+	}
 }
 
+/// The longest a summary gets before it is cut off at a word.
+const SUMMARY_MAX_LEN: usize = 200;
+
 impl<T: Ord> PartialOrd for ContentItem<T> {
 	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
 		Some(self.cmp(other))
@@ -59,10 +138,23 @@ pub trait Content: Sized {
 	type FrontMatter: serde::de::DeserializeOwned + Ord;
 	fn content() -> IncludeDir;
 
+	/// How the markdown of this content is typeset, which a post can override by setting `smart_punctuation` or `emoji_shortcodes` in its front matter.
+	#[must_use]
+	fn typography() -> ui::Typography {
+		ui::Typography::default()
+	}
+
+	/// Code blocks with more lines than this are folded until a reader expands them.
+	#[must_use]
+	fn code_fold_lines() -> Option<usize> {
+		None
+	}
+
 	fn slugs() -> Result<Vec<String>> {
 		let content = Self::content();
 		let slug_and_paths = content
 			.into_iter()
+			.filter(|(entry, _)| entry.file_name().is_some_and(|name| name == "post.md"))
 			.map(|(entry, _)| {
 				entry
 					.parent()
@@ -77,6 +169,18 @@ pub trait Content: Sized {
 		Ok(slug_and_paths)
 	}
 
+	/// A file in the directory of the post with `slug`, like a video it plays.
+	#[must_use]
+	fn file(slug: &str, name: &str) -> Option<Cow<'static, [u8]>> {
+		// Only files directly in a post's directory are read, never a path that climbs out of it.
+		let is_file_name =
+			|name: &str| !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\']);
+		if !is_file_name(slug) || !is_file_name(name) {
+			return None;
+		}
+		Some(Self::content().read(&Path::new(slug).join(name))?.data())
+	}
+
 	fn list() -> Result<Vec<ContentItem<Self::FrontMatter>>> {
 		let mut list = Self::slugs()?
 			.into_iter()
@@ -87,36 +191,77 @@ pub trait Content: Sized {
 		Ok(list)
 	}
 
+	/// The `post.md` of the post with `slug`, front matter and all, with its includes expanded.
+	fn source(slug: &str) -> Result<String> {
+		let post_path = Path::new(slug).join("post.md");
+		let post = Self::content()
+			.read(&post_path)
+			.ok_or_else(|| anyhow!("missing {}", post_path.display()))?
+			.data();
+		let post_str = std::str::from_utf8(&post)?;
+		include::expand_includes(post_str, |path| {
+			let file = Self::content().read(&Path::new(slug).join(path))?.data();
+			Some(String::from_utf8_lossy(&file).into_owned())
+		})
+	}
+
 	fn from_slug(slug: String) -> Result<ContentItem<Self::FrontMatter>> {
 		let post_path = Path::new(&slug).join("post.md");
-		let post = Self::content().read(&post_path).unwrap().data();
-		let post_str = std::str::from_utf8(&post)?.to_owned();
+		let post_str = Self::source(&slug)?;
 		let (front_matter, markdown) = parse_and_find_content(&post_str)?;
-		let front_matter = serde_yaml::from_reader(front_matter)?;
+		let front_matter = std::io::read_to_string(front_matter)?;
+		let TypographyFrontMatter {
+			smart_punctuation,
+			emoji_shortcodes,
+		} = serde_yaml::from_str(&front_matter)?;
+		let mut typography = Self::typography();
+		typography.smart_punctuation = smart_punctuation.unwrap_or(typography.smart_punctuation);
+		typography.emoji_shortcodes = emoji_shortcodes.unwrap_or(typography.emoji_shortcodes);
+		let markdown = markdown
+			.typography(typography)
+			.code_fold_lines(Self::code_fold_lines());
+		let front_matter = serde_yaml::from_str(&front_matter)?;
+		let excerpt = markdown.excerpt();
+		let summary = summarize(&excerpt.plain_text());
 		let ret = ContentItem {
 			path: post_path,
 			slug,
 			front_matter,
 			markdown,
+			excerpt,
+			summary,
 		};
 		Ok(ret)
 	}
 }
 
+/// Cut `text` off at the last word that fits in a summary.
+fn summarize(text: &str) -> String {
+	if text.len() <= SUMMARY_MAX_LEN {
+		return text.to_owned();
+	}
+	let mut end = SUMMARY_MAX_LEN;
+	while !text.is_char_boundary(end) {
+		end -= 1;
+	}
+	let cut = text[..end].rfind(' ').unwrap_or(end);
+	let summary = text[..cut].trim_end_matches(|c: char| c.is_ascii_punctuation());
+	format!("{summary}…")
+}
+
 fn find_yaml_block(text: &str) -> Option<(usize, usize, usize)> {
 	let marker = "---\n";
 	let marker_len = marker.len();
-	match text.starts_with(marker) {
-		true => {
-			let slice_after_marker = &text[marker_len..];
-			let front_matter_end = slice_after_marker.find(marker)?;
-			Some((
-				marker_len,
-				front_matter_end + marker_len,
-				front_matter_end + 2 * marker_len,
-			))
-		}
-		false => None,
+	if text.starts_with(marker) {
+		let slice_after_marker = &text[marker_len..];
+		let front_matter_end = slice_after_marker.find(marker)?;
+		Some((
+			marker_len,
+			front_matter_end + marker_len,
+			front_matter_end + 2 * marker_len,
+		))
+	} else {
+		None
 	}
 }
 
@@ -127,7 +272,7 @@ pub fn parse_and_find_content(text: &str) -> Result<(impl std::io::Read + '_, ui
 			let yaml_str = &text[front_matter_start..front_matter_end];
 			let front_matter = yaml_str.as_bytes();
 			let post = text[content_start..].to_string();
-			let post = ui::Markdown::new(post);
+			let post = ui::Markdown::new(post).html_policy(ui::HtmlPolicy::Trusted);
 			Ok((front_matter, post))
 		}
 		None => Err(anyhow!("Invalid YAML frontmatter")),
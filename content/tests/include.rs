@@ -0,0 +1,45 @@
+use deciduously_com_sunfish_content::expand_includes;
+use std::path::Path;
+
+const MAIN: &str = "use std::fmt;\n// ANCHOR: body\nfn main() {}\n// ANCHOR_END: body\n// ANCHOR: open\nlet x = 1;\n";
+
+fn read(path: &Path) -> Option<String> {
+	(path == Path::new("src/main.rs")).then(|| MAIN.to_owned())
+}
+
+#[test]
+fn includes_files_lines_and_anchors() {
+	assert_eq!(
+		expand_includes("Before.\n{{#include src/main.rs:body}}\nAfter.\n", read).unwrap(),
+		"Before.\n```rust,title=\"src/main.rs\"\nfn main() {}\n```\nAfter.\n"
+	);
+	assert_eq!(
+		expand_includes("{{#include src/main.rs:1}}\n", read).unwrap(),
+		"```rust,title=\"src/main.rs\"\nuse std::fmt;\n```\n"
+	);
+	assert_eq!(
+		expand_includes("{{#include src/main.rs:5:}}\n", read).unwrap(),
+		"```rust,title=\"src/main.rs\"\nlet x = 1;\n```\n"
+	);
+	let fenced = "```\n{{#include src/main.rs}}\n```\n";
+	assert_eq!(expand_includes(fenced, read).unwrap(), fenced);
+}
+
+#[test]
+fn reports_includes_that_select_nothing() {
+	for directive in [
+		"src/main.rs:open",
+		"src/main.rs:missing",
+		"src/main.rs:0",
+		"src/main.rs:5:2",
+		"src/main.rs:x:1",
+		"src/other.rs",
+		"../secret.rs",
+		"/etc/passwd",
+	] {
+		let markdown = format!("{{{{#include {directive}}}}}\n");
+		assert!(expand_includes(&markdown, read).is_err(), "{directive}");
+	}
+	let same_line = |_: &Path| Some("// ANCHOR: a ANCHOR_END: a\n".to_owned());
+	assert!(expand_includes("{{#include f.rs:a}}\n", same_line).is_err());
+}
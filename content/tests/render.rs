@@ -0,0 +1,36 @@
+use deciduously_com_sunfish_content::{BlogPost, Content};
+
+#[test]
+fn every_post_renders_without_warnings() {
+	let mut slugs = BlogPost::slugs().unwrap();
+	assert_ne!(slugs.len(), 0);
+	slugs.sort();
+	let mut failures = Vec::new();
+	for slug in slugs {
+		let blog_post = BlogPost::from_slug(slug.clone()).unwrap();
+		for warning in blog_post.markdown.render().warnings {
+			failures.push(format!("{slug}/post.md: {warning}"));
+		}
+	}
+	assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn renders_the_blog_index_once_with_unique_ids() {
+	let index = BlogPost::index().unwrap();
+	assert_eq!(index.len(), BlogPost::slugs().unwrap().len());
+	assert!(std::ptr::eq(index, BlogPost::index().unwrap()));
+	let html = index
+		.iter()
+		.map(|entry| entry.excerpt.html.as_str())
+		.collect::<String>();
+	let mut ids = html
+		.split("id=\"")
+		.skip(1)
+		.filter_map(|rest| rest.split('"').next())
+		.collect::<Vec<_>>();
+	let len = ids.len();
+	ids.sort_unstable();
+	ids.dedup();
+	assert_eq!(ids.len(), len);
+}
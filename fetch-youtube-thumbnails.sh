@@ -0,0 +1,9 @@
+#!/bin/sh
+# Download the thumbnails of the YouTube videos embedded in posts, so the site serves them itself instead of loading them from YouTube on every page view.
+set -e
+mkdir -p static/youtube
+grep -rhoE '\{% *youtube +[A-Za-z0-9_-]+' content/blog | awk '{ print $NF }' | sort -u | while read -r id; do
+	if [ ! -f "static/youtube/$id.jpg" ]; then
+		curl -fsSL -o "static/youtube/$id.jpg" "https://i.ytimg.com/vi/$id/hqdefault.jpg"
+	fi
+done
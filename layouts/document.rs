@@ -1,5 +1,6 @@
 //! Toplevel Document layout.
 
+use deciduously_com_sunfish_ui as ui;
 use pinwheel::prelude::*;
 
 #[derive(builder, Default, children, new)]
@@ -7,11 +8,21 @@ use pinwheel::prelude::*;
 pub struct Document {
 	#[builder]
 	pub client: Option<&'static str>,
+	/// The syntax highlighting themes for the light and dark colour schemes, defaulting to the site's own.
+	#[builder]
+	pub highlight_themes: Option<ui::HighlightThemes>,
+	/// The description of the page for search engines and link previews, defaulting to the site's.
+	#[builder]
+	pub description: Option<String>,
 	pub children: Vec<Node>,
 }
 
 impl Component for Document {
 	fn into_node(self) -> Node {
+		let highlight_themes = self.highlight_themes.unwrap_or_default();
+		let description = self
+			.description
+			.unwrap_or_else(|| "deciduously.com | blog | projects".to_owned());
 		let head = head()
 			.child(meta().attribute("charset", "utf-8"))
 			.child(
@@ -35,12 +46,20 @@ impl Component for Document {
 					.attribute("href", "/styles.css")
 					.attribute("rel", "stylesheet"),
 			)
+			.child(style().inner_html(highlight_themes.css()))
 			.child(
 				meta()
-					.attribute("content", "deciduously.com | blog | projects")
+					.attribute("content", description)
 					.attribute("name", "description"),
 			);
-		let body = body().child(self.children);
+		let client_script = self.client.map(|client| {
+			let paths = sunfish::client_paths(client);
+			script().attribute("type", "module").inner_html(format!(
+				r#"import init from "{}"; init("{}")"#,
+				paths.path_js, paths.path_wasm
+			))
+		});
+		let body = body().child(self.children).child(client_script);
 		html::html()
 			.attribute("lang", "en")
 			.child(head)
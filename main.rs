@@ -1,21 +1,33 @@
 #![warn(clippy::pedantic)]
 
-use clap::Parser;
+use anyhow::bail;
+use clap::{Parser, Subcommand};
+use deciduously_com_sunfish_content::{BlogPost, Content};
+use deciduously_com_sunfish_ui as ui;
 use std::sync::Arc;
 use sunfish::Sunfish;
 use tracing_subscriber::prelude::*;
 
+mod check_snippets;
 mod serve;
 
 /// Operating characteristics of the server.
 #[derive(Parser)]
 struct Args {
+	#[clap(subcommand)]
+	command: Option<Command>,
 	#[clap(env, long, help = "Host IP to bind", default_value = "0.0.0.0")]
 	host: String,
 	#[clap(env, long, help = "Port to bind", default_value = "8080")]
 	port: u16,
 }
 
+#[derive(Subcommand)]
+enum Command {
+	/// Compile the Rust snippets embedded in blog posts.
+	CheckSnippets(check_snippets::Args),
+}
+
 struct Context {
 	sunfish: Sunfish,
 }
@@ -23,7 +35,11 @@ struct Context {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
 	let args = Args::parse();
+	if let Some(Command::CheckSnippets(args)) = args.command {
+		return check_snippets::check_snippets(&args);
+	}
 	setup_tracing();
+	render_posts()?;
 	let sunfish = sunfish::init!();
 	let host = args.host.parse()?;
 	let addr = std::net::SocketAddr::new(host, args.port);
@@ -33,6 +49,21 @@ async fn main() -> anyhow::Result<()> {
 	Ok(())
 }
 
+/// Render every post and the blog index before serving, so requests only read highlighted code from the cache and the index is served as rendered here. A post that cannot be read or highlighted stops startup, and anything else a post renders differently than written is logged.
+fn render_posts() -> anyhow::Result<()> {
+	for slug in BlogPost::slugs()? {
+		let blog_post = BlogPost::from_slug(slug.clone())?;
+		for warning in blog_post.markdown.render().warnings {
+			if warning.kind == ui::MarkdownWarningKind::Highlight {
+				bail!("{slug}/post.md: {warning}");
+			}
+			tracing::warn!(%warning, "{slug}/post.md");
+		}
+	}
+	BlogPost::index()?;
+	Ok(())
+}
+
 fn setup_tracing() {
 	let env_layer = tracing_subscriber::filter::EnvFilter::try_from_env("deciduously_com_TRACING");
 	let env_layer = if cfg!(debug_assertions) {
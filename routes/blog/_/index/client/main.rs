@@ -0,0 +1,22 @@
+use deciduously_com_sunfish_ui as ui;
+use pinwheel::prelude::*;
+
+fn main() {
+	console_error_panic_hook::set_once();
+	let document = web_sys::window().unwrap().document().unwrap();
+	// The server numbers the copy buttons of a post's code blocks and its video embeds from zero.
+	for index in 0.. {
+		let id = format!("code-copy-{index}");
+		if document.get_element_by_id(&id).is_none() {
+			break;
+		}
+		hydrate::<ui::CopyButton>(&id);
+	}
+	for index in 0.. {
+		let id = format!("video-embed-{index}");
+		if document.get_element_by_id(&id).is_none() {
+			break;
+		}
+		hydrate::<ui::YouTubeEmbed>(&id);
+	}
+}
@@ -15,6 +15,7 @@ pub struct Page {
 impl Component for Page {
 	fn into_node(self) -> Node {
 		let blog_post = BlogPost::from_slug(self.slug).unwrap();
+		let description = blog_post.description().to_owned();
 		let tags = if let Some(tags) = blog_post.front_matter.tags {
 			let tags = tags.iter().map(|tag| format!("#{tag}")).collect::<Vec<_>>();
 			div().class("tags").child(tags.join(", "))
@@ -38,6 +39,8 @@ impl Component for Page {
 			.child(cover_image)
 			.child(tags);
 		Document::new()
+			.client("deciduously_com_sunfish_blog_client")
+			.description(description)
 			.child(
 				PageLayout::new().child(
 					div()
@@ -1,4 +1,4 @@
-use deciduously_com_sunfish_content::{BlogPost, Content};
+use deciduously_com_sunfish_content::BlogPost;
 use deciduously_com_sunfish_layouts::{document::Document, page_layout::PageLayout};
 use deciduously_com_sunfish_ui as ui;
 use pinwheel::prelude::*;
@@ -11,16 +11,21 @@ const DATE_FORMAT: &[FormatItem<'static>] =
 
 impl Component for Page {
 	fn into_node(self) -> Node {
-		let blog_posts = BlogPost::list().unwrap().into_iter().map(|blog_post| {
+		let blog_posts = BlogPost::index().unwrap().iter().map(|blog_post| {
 			let date = blog_post.front_matter.date.format(&DATE_FORMAT).unwrap();
 			let href = format!("/blog/{}/", blog_post.slug);
 			div()
 				.child(
 					ui::Link::new()
 						.href(href)
-						.child(blog_post.front_matter.title),
+						.child(blog_post.front_matter.title.clone()),
 				)
 				.child(p().child(date))
+				.child(
+					div()
+						.class("blog-post-excerpt")
+						.child(blog_post.excerpt.clone()),
+				)
 		});
 		Document::new()
 			.child(
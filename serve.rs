@@ -1,10 +1,12 @@
 //! Helper functions to serve teh Sunfish app.
 use crate::Context;
 use backtrace::Backtrace;
+use deciduously_com_sunfish_content::{BlogPost, Content};
 use futures::{future::FutureExt, Future};
 use std::{
 	cell::RefCell,
 	convert::Infallible,
+	ops::Range,
 	panic::AssertUnwindSafe,
 	sync::{
 		atomic::{self, AtomicUsize},
@@ -38,12 +40,65 @@ async fn handle(mut request: http::Request<hyper::Body>) -> http::Response<hyper
 					.unwrap(),
 			)
 		});
-	response.unwrap_or_else(|| {
-		http::Response::builder()
-			.status(http::StatusCode::NOT_FOUND)
-			.body(hyper::Body::from("not found"))
-			.unwrap()
-	})
+	response
+		.or_else(|| serve_post_video(&request))
+		.unwrap_or_else(|| {
+			http::Response::builder()
+				.status(http::StatusCode::NOT_FOUND)
+				.body(hyper::Body::from("not found"))
+				.unwrap()
+		})
+}
+
+/// Serve a video from a post's directory, like `/blog/a_post/demo.mp4`, which sunfish does not bundle as an asset. Browsers seek in a video with range requests, so a single range is supported.
+fn serve_post_video(request: &http::Request<hyper::Body>) -> Option<http::Response<hyper::Body>> {
+	let ["blog", slug, name] = *sunfish::path_components(request.uri().path()).as_slice() else {
+		return None;
+	};
+	let content_type = match name.rsplit_once('.')?.1 {
+		"m4v" | "mp4" => "video/mp4",
+		"ogv" => "video/ogg",
+		"webm" => "video/webm",
+		_ => return None,
+	};
+	let data = BlogPost::file(slug, name)?;
+	let len = data.len();
+	let range = request
+		.headers()
+		.get(http::header::RANGE)
+		.and_then(|range| parse_range(range.to_str().ok()?, len));
+	let response = http::Response::builder()
+		.header(http::header::ACCEPT_RANGES, "bytes")
+		.header(http::header::CONTENT_TYPE, content_type);
+	let response = match range {
+		Some(range) => response
+			.status(http::StatusCode::PARTIAL_CONTENT)
+			.header(
+				http::header::CONTENT_RANGE,
+				format!("bytes {}-{}/{len}", range.start, range.end - 1),
+			)
+			.body(hyper::Body::from(data[range].to_vec())),
+		None => response
+			.status(http::StatusCode::OK)
+			.body(hyper::Body::from(data)),
+	};
+	response.ok()
+}
+
+/// Parse a `Range` header asking for one range of bytes, like `bytes=0-`, `bytes=100-199` or `bytes=-500`. Anything else is answered with the whole file.
+fn parse_range(header: &str, len: usize) -> Option<Range<usize>> {
+	let (start, end) = header.strip_prefix("bytes=")?.split_once('-')?;
+	let range = if start.is_empty() {
+		len.saturating_sub(end.parse().ok()?)..len
+	} else {
+		let end = if end.is_empty() {
+			len
+		} else {
+			end.parse::<usize>().ok()?.saturating_add(1).min(len)
+		};
+		start.parse().ok()?..end
+	};
+	(range.start < range.end).then_some(range)
 }
 
 static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
@@ -0,0 +1,136 @@
+use crate::sanitize::push_escaped;
+
+/// The text attributes selected by ANSI SGR escape sequences. Colors are indexes into the 16 color palette, where 8 to 15 are the bright variants of 0 to 7.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Style {
+	foreground: Option<u8>,
+	background: Option<u8>,
+	bold: bool,
+	underline: bool,
+}
+
+impl Style {
+	fn apply(&mut self, parameters: &str) {
+		let mut codes = parameters
+			.split(';')
+			.map(|code| code.parse::<u8>().unwrap_or(0));
+		while let Some(code) = codes.next() {
+			match code {
+				0 => *self = Style::default(),
+				1 => self.bold = true,
+				4 => self.underline = true,
+				22 => self.bold = false,
+				24 => self.underline = false,
+				30..=37 => self.foreground = Some(code - 30),
+				39 => self.foreground = None,
+				40..=47 => self.background = Some(code - 40),
+				49 => self.background = None,
+				90..=97 => self.foreground = Some(code - 90 + 8),
+				100..=107 => self.background = Some(code - 100 + 8),
+				// The first 16 of the 256 colors are the palette, which tools built on termcolor select this way. The rest, and truecolor, have no equivalent in the palette, so their arguments are skipped.
+				38 | 48 => {
+					let color = match codes.next() {
+						Some(5) => codes.next().filter(|index| *index < 16),
+						Some(2) => {
+							codes.by_ref().take(3).for_each(drop);
+							None
+						}
+						_ => None,
+					};
+					if let Some(color) = color {
+						if code == 38 {
+							self.foreground = Some(color);
+						} else {
+							self.background = Some(color);
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	/// The classes that style text like this, backed by the `--terminal-*` colors of the current theme.
+	fn classes(self) -> String {
+		let mut classes = Vec::new();
+		if let Some(foreground) = self.foreground {
+			classes.push(["ansi-", COLOR_NAMES[usize::from(foreground)]].concat());
+		}
+		if let Some(background) = self.background {
+			classes.push(["ansi-bg-", COLOR_NAMES[usize::from(background)]].concat());
+		}
+		if self.bold {
+			classes.push("ansi-bold".to_owned());
+		}
+		if self.underline {
+			classes.push("ansi-underline".to_owned());
+		}
+		classes.join(" ")
+	}
+}
+
+/// The names of the palette's colors in its classes and `--terminal-*` variables.
+const COLOR_NAMES: [&str; 16] = [
+	"black",
+	"red",
+	"green",
+	"yellow",
+	"blue",
+	"magenta",
+	"cyan",
+	"white",
+	"bright-black",
+	"bright-red",
+	"bright-green",
+	"bright-yellow",
+	"bright-blue",
+	"bright-magenta",
+	"bright-cyan",
+	"bright-white",
+];
+
+/// Escape sequences are often pasted in their textual form rather than as a literal escape character.
+const ESCAPES: &[&str] = &["\u{1b}[", "\\x1b[", "\\u001b[", "\\033[", "\\e["];
+
+/// Render terminal output containing ANSI SGR escape sequences as html with styled spans. Other escape sequences are dropped.
+#[must_use]
+pub fn ansi_to_html(text: &str) -> String {
+	let mut html = String::new();
+	let mut style = Style::default();
+	let mut rest = text;
+	loop {
+		let next_escape = ESCAPES
+			.iter()
+			.filter_map(|escape| rest.find(escape).map(|index| (index, escape.len())))
+			.min();
+		let Some((index, escape_len)) = next_escape else {
+			push_text(&mut html, rest, style);
+			break;
+		};
+		push_text(&mut html, &rest[..index], style);
+		rest = &rest[index + escape_len..];
+		let sequence_len = rest
+			.find(|c: char| c.is_ascii_alphabetic())
+			.map_or(rest.len(), |index| index + 1);
+		if rest[..sequence_len].ends_with('m') {
+			style.apply(&rest[..sequence_len - 1]);
+		}
+		rest = &rest[sequence_len..];
+	}
+	html
+}
+
+fn push_text(html: &mut String, text: &str, style: Style) {
+	if text.is_empty() {
+		return;
+	}
+	if style == Style::default() {
+		push_escaped(html, text);
+	} else {
+		html.push_str("<span class=\"");
+		html.push_str(&style.classes());
+		html.push_str("\">");
+		push_escaped(html, text);
+		html.push_str("</span>");
+	}
+}
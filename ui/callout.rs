@@ -0,0 +1,91 @@
+use crate::colors;
+use pinwheel::prelude::*;
+
+/// A box that sets a note or warning apart from the text around it, written in markdown as a GitHub-style `> [!NOTE]` blockquote.
+#[derive(builder, children, new)]
+pub struct Callout {
+	pub kind: CalloutKind,
+	/// The title shown next to the icon, which defaults to the name of the kind.
+	#[builder]
+	#[new(default)]
+	pub title: Option<String>,
+	#[new(default)]
+	pub children: Vec<Node>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalloutKind {
+	Note,
+	Tip,
+	Important,
+	Warning,
+	Caution,
+}
+
+impl CalloutKind {
+	/// The kind named in a `[!NOTE]` marker, in any case.
+	#[must_use]
+	pub fn parse(name: &str) -> Option<CalloutKind> {
+		match name.to_ascii_lowercase().as_str() {
+			"note" => Some(CalloutKind::Note),
+			"tip" => Some(CalloutKind::Tip),
+			"important" => Some(CalloutKind::Important),
+			"warning" => Some(CalloutKind::Warning),
+			"caution" => Some(CalloutKind::Caution),
+			_ => None,
+		}
+	}
+
+	#[must_use]
+	pub fn title(self) -> &'static str {
+		match self {
+			CalloutKind::Note => "Note",
+			CalloutKind::Tip => "Tip",
+			CalloutKind::Important => "Important",
+			CalloutKind::Warning => "Warning",
+			CalloutKind::Caution => "Caution",
+		}
+	}
+
+	fn color(self) -> &'static str {
+		match self {
+			CalloutKind::Note => colors::BLUE,
+			CalloutKind::Tip => colors::GREEN,
+			CalloutKind::Important => colors::PURPLE,
+			CalloutKind::Warning => colors::ORANGE,
+			CalloutKind::Caution => colors::RED,
+		}
+	}
+
+	fn icon(self) -> &'static str {
+		match self {
+			CalloutKind::Note => "i",
+			CalloutKind::Tip => "✓",
+			CalloutKind::Important | CalloutKind::Warning => "!",
+			CalloutKind::Caution => "×",
+		}
+	}
+}
+
+impl Component for Callout {
+	fn into_node(self) -> Node {
+		let class = format!("callout callout-{}", self.kind.title().to_lowercase());
+		let title = self.title.unwrap_or_else(|| self.kind.title().to_owned());
+		div()
+			.class(class)
+			.attribute("style", format!("--callout-color: {};", self.kind.color()))
+			.child(
+				div()
+					.class("callout-title")
+					.child(
+						span()
+							.class("callout-icon")
+							.attribute("aria-hidden", "true")
+							.child(self.kind.icon()),
+					)
+					.child(title),
+			)
+			.child(self.children)
+			.into_node()
+	}
+}
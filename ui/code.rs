@@ -1,19 +1,29 @@
+use crate::{self as ui, sanitize};
 use pinwheel::prelude::*;
-use std::borrow::Cow;
+use std::{borrow::Cow, ops::RangeInclusive};
 
 #[derive(builder, Default, new)]
 #[new(default)]
 pub struct Code {
+	#[builder]
+	pub ansi: Option<bool>,
 	#[builder]
 	pub code: Option<Cow<'static, str>>,
 	#[builder]
+	pub diff: Option<bool>,
+	#[builder]
+	pub highlight_lines: Option<Vec<RangeInclusive<usize>>>,
+	#[builder]
 	pub language: Option<Language>,
 	#[builder]
 	pub line_numbers: Option<bool>,
+	#[builder]
+	pub shell_session: Option<bool>,
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Language {
+	Bash,
 	Elixir,
 	Go,
 	Javascript,
@@ -23,24 +33,303 @@ pub enum Language {
 	Rust,
 }
 
+impl Language {
+	/// Map the language name from a fenced code block to a highlighting grammar.
+	#[must_use]
+	pub fn from_name(name: &str) -> Option<Language> {
+		match name {
+			"bash" | "sh" | "shell" => Some(Language::Bash),
+			"elixir" => Some(Language::Elixir),
+			"go" => Some(Language::Go),
+			"javascript" | "js" => Some(Language::Javascript),
+			"php" => Some(Language::Php),
+			"python" => Some(Language::Python),
+			"ruby" => Some(Language::Ruby),
+			"rust" => Some(Language::Rust),
+			_ => None,
+		}
+	}
+}
+
 impl Component for Code {
 	fn into_node(self) -> Node {
-		let mut code = self.code.unwrap_or(Cow::Borrowed(""));
-		if let Some(language) = self.language {
-			code = highlight(code.as_ref(), language).into();
+		let code = self.code.unwrap_or(Cow::Borrowed(""));
+		let count = count_lines(&code);
+		let ansi = self.ansi.unwrap_or(false);
+		let session = if self.shell_session.unwrap_or(false) {
+			parse_session(&code)
+		} else {
+			None
+		};
+		let diff = if self.diff.unwrap_or(false) {
+			Some(parse_diff(&code))
+		} else {
+			None
+		};
+		// Each line is rendered as a class to add to the line and its html.
+		let lines: Vec<(&str, String)> = if let Some((session_lines, commands, output)) = &session {
+			let commands = split_lines(&source_html(commands, self.language, false));
+			let output = split_lines(&source_html(output, None, ansi));
+			session_lines
+				.iter()
+				.zip(commands.into_iter().zip(output))
+				.map(|(session_line, (command, output))| match session_line {
+					SessionLine::Prompt(prompt) => (
+						"code-line-command",
+						format!(
+							"<span class=\"code-prompt\">{}</span>{command}",
+							escape(prompt)
+						),
+					),
+					SessionLine::Continuation => ("code-line-command", command),
+					SessionLine::Output => ("code-line-output", output),
+				})
+				.collect()
+		} else if let Some((diff_lines, source)) = &diff {
+			let lines = split_lines(&source_html(source, self.language, ansi));
+			diff_lines
+				.iter()
+				.zip(lines)
+				.map(|(diff_line, line)| {
+					let (marker, class) = match diff_line {
+						DiffLine::Hunk(header) => return ("code-line-hunk", escape(header)),
+						DiffLine::Added => ('+', "code-line-added"),
+						DiffLine::Removed => ('-', "code-line-removed"),
+						DiffLine::Context => (' ', "code-line-context"),
+					};
+					(
+						class,
+						format!("<span class=\"code-diff-marker\">{marker}</span>{line}"),
+					)
+				})
+				.collect()
+		} else {
+			split_lines(&source_html(&code, self.language, ansi))
+				.into_iter()
+				.map(|line| ("", line))
+				.collect()
+		};
+		let highlight_lines = self.highlight_lines.unwrap_or_default();
+		let mut inner = String::new();
+		for (index, (line_class, line)) in lines.iter().take(count).enumerate() {
+			if index > 0 {
+				inner.push('\n');
+			}
+			let number = index + 1;
+			let mut class = String::from("code-line");
+			if highlight_lines.iter().any(|range| range.contains(&number)) {
+				class.push_str(" code-line-highlighted");
+			}
+			if !line_class.is_empty() {
+				class.push(' ');
+				class.push_str(line_class);
+			}
+			inner.push_str(&["<span class=\"", &class, "\">", line, "</span>"].concat());
 		}
 		let line_numbers = self.line_numbers.unwrap_or(false);
 		let line_numbers = if line_numbers {
-			Some(LineNumbers {
-				count: count_lines(&code),
-			})
+			Some(LineNumbers { count })
 		} else {
 			None
 		};
 		div()
 			.class("code")
 			.child(line_numbers)
-			.child(div().class("code-inner").inner_html(code))
+			.child(div().class("code-inner").inner_html(inner))
+			.into_node()
+	}
+}
+
+fn source_html(source: &str, language: Option<Language>, ansi: bool) -> String {
+	if ansi {
+		ui::ansi_to_html(source)
+	} else if let Some(language) = language {
+		highlight(source, language)
+	} else {
+		escape(source)
+	}
+}
+
+enum DiffLine<'a> {
+	Added,
+	Removed,
+	Context,
+	Hunk(&'a str),
+}
+
+/// Classify each line of a unified diff and strip its `+`/`-`/` ` marker, returning the remaining source so it can be highlighted as a whole. A `--- a` and `+++ b` pair is a file header only before the first `@@` hunk or after a `diff` or `index` line, so removed and added lines that start with `--` or `++` are still classified by their marker.
+fn parse_diff(code: &str) -> (Vec<DiffLine<'_>>, String) {
+	let code_lines = code.lines().collect::<Vec<_>>();
+	let mut lines = Vec::new();
+	let mut source = String::new();
+	// Whether file headers can come next, which they can before the first hunk and after the start of another file.
+	let mut in_header = true;
+	let mut after_old_file = false;
+	for (index, line) in code_lines.iter().copied().enumerate() {
+		let is_old_file = in_header
+			&& line.starts_with("--- ")
+			&& code_lines
+				.get(index + 1)
+				.is_some_and(|next| next.starts_with("+++ "));
+		let is_new_file = after_old_file && line.starts_with("+++ ");
+		after_old_file = is_old_file;
+		let (diff_line, rest) = if line.starts_with("@@") {
+			in_header = false;
+			(DiffLine::Hunk(line), "")
+		} else if line.starts_with("diff ") || line.starts_with("index ") {
+			in_header = true;
+			(DiffLine::Hunk(line), "")
+		} else if is_old_file || is_new_file {
+			(DiffLine::Hunk(line), "")
+		} else if let Some(rest) = line.strip_prefix('+') {
+			(DiffLine::Added, rest)
+		} else if let Some(rest) = line.strip_prefix('-') {
+			(DiffLine::Removed, rest)
+		} else {
+			(DiffLine::Context, line.strip_prefix(' ').unwrap_or(line))
+		};
+		lines.push(diff_line);
+		source.push_str(rest);
+		source.push('\n');
+	}
+	(lines, source)
+}
+
+/// The length of the prompt a line of a shell session starts with: `$ `, or one naming the user and host like `root@host:/dir# `, as in a shell in a container or on another machine.
+fn prompt_len(line: &str) -> Option<usize> {
+	if line == "$" {
+		return Some(1);
+	}
+	if line.starts_with("$ ") {
+		return Some(2);
+	}
+	let (user, host) = line.split_once('@')?;
+	if user.is_empty()
+		|| !user
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+	{
+		return None;
+	}
+	let host_len = host.find(char::is_whitespace).unwrap_or(host.len());
+	if !host[..host_len].ends_with(['$', '#']) {
+		return None;
+	}
+	let len = user.len() + 1 + host_len;
+	if len == line.len() {
+		Some(len)
+	} else {
+		line[len..].starts_with(' ').then_some(len + 1)
+	}
+}
+
+enum SessionLine<'a> {
+	Prompt(&'a str),
+	Continuation,
+	Output,
+}
+
+/// Separate a shell session into the commands typed after prompts and their output, returning `None` if there are no prompts. Both sources keep every line so they can be rendered separately and zipped back together.
+fn parse_session(code: &str) -> Option<(Vec<SessionLine<'_>>, String, String)> {
+	let mut lines = Vec::new();
+	let mut commands = String::new();
+	let mut output = String::new();
+	let mut continued = false;
+	for line in code.lines() {
+		if let Some(prompt_len) = prompt_len(line) {
+			lines.push(SessionLine::Prompt(&line[..prompt_len]));
+			commands.push_str(&line[prompt_len..]);
+			continued = line.ends_with('\\');
+		} else if continued {
+			lines.push(SessionLine::Continuation);
+			commands.push_str(line);
+			continued = line.ends_with('\\');
+		} else {
+			lines.push(SessionLine::Output);
+			output.push_str(line);
+		}
+		commands.push('\n');
+		output.push('\n');
+	}
+	if lines
+		.iter()
+		.any(|line| matches!(line, SessionLine::Prompt(_)))
+	{
+		Some((lines, commands, output))
+	} else {
+		None
+	}
+}
+
+/// A long code block cut short until the reader expands it. The toggle is a checkbox, so expanding needs no javascript.
+#[derive(new)]
+pub struct CodeFold {
+	pub id: String,
+	pub code: Code,
+}
+
+impl Component for CodeFold {
+	fn into_node(self) -> Node {
+		let lines = self.code.code.as_deref().map_or(0, count_lines);
+		div()
+			.class("code-fold")
+			.child(
+				input()
+					.class("code-fold-input")
+					.attribute("type", "checkbox")
+					.attribute("id", self.id.clone()),
+			)
+			.child(div().class("code-fold-content").child(self.code))
+			.child(
+				label()
+					.class("code-fold-toggle")
+					.attribute("for", self.id)
+					.child(
+						span()
+							.class("code-fold-expand")
+							.child(format!("Show all {lines} lines")),
+					)
+					.child(span().class("code-fold-collapse").child("Show less")),
+			)
+			.into_node()
+	}
+}
+
+/// The filename or language shown above a code block.
+#[derive(builder, Default, new)]
+#[new(default)]
+pub struct CodeHeader {
+	#[builder]
+	pub copy_button: Option<Node>,
+	#[builder]
+	pub language: Option<String>,
+	#[builder]
+	pub playground: Option<String>,
+	#[builder]
+	pub title: Option<String>,
+}
+
+impl Component for CodeHeader {
+	fn into_node(self) -> Node {
+		div()
+			.class("code-header")
+			.child(
+				self.title
+					.map(|title| span().class("code-header-title").child(title)),
+			)
+			.child(
+				self.language
+					.map(|language| span().class("code-header-language").child(language)),
+			)
+			.child(self.playground.map(|playground| {
+				ui::Link::new()
+					.class("code-header-playground".to_owned())
+					.href(playground)
+					.target("_blank".to_owned())
+					.title("Run this code on the Rust Playground".to_owned())
+					.child("Run".to_owned())
+			}))
+			.child(self.copy_button)
 			.into_node()
 	}
 }
@@ -64,21 +353,38 @@ impl Component for LineNumbers {
 
 pub struct InlineCodeBlock {
 	pub code: Cow<'static, str>,
+	pub language: Option<Language>,
 }
 
 impl InlineCodeBlock {
 	pub fn new(code: impl Into<Cow<'static, str>>) -> InlineCodeBlock {
-		InlineCodeBlock { code: code.into() }
+		InlineCodeBlock {
+			code: code.into(),
+			language: None,
+		}
+	}
+
+	#[must_use]
+	pub fn language(mut self, language: impl Into<Option<Language>>) -> InlineCodeBlock {
+		self.language = language.into();
+		self
 	}
 }
 
 impl Component for InlineCodeBlock {
 	fn into_node(self) -> Node {
-		span().class("inline-code").child(self.code).into_node()
+		let inline_code = span().class("inline-code");
+		if let Some(language) = self.language {
+			inline_code
+				.inner_html(highlight_inline(&self.code, language))
+				.into_node()
+		} else {
+			inline_code.child(self.code).into_node()
+		}
 	}
 }
 
-fn count_lines(text: &str) -> usize {
+pub(crate) fn count_lines(text: &str) -> usize {
 	let n_lines = text.split('\n').count();
 	if text.ends_with('\n') {
 		n_lines - 1
@@ -87,6 +393,145 @@ fn count_lines(text: &str) -> usize {
 	}
 }
 
+/// How rustdoc treats a line of a Rust code block: lines starting with `# ` are hidden from readers, and a leading `##` escapes a literal `#`.
+enum RustdocLine<'a> {
+	Hidden(&'a str),
+	Visible(Cow<'a, str>),
+}
+
+fn rustdoc_line(line: &str) -> RustdocLine<'_> {
+	let trimmed = line.trim_start();
+	let indent = &line[..line.len() - trimmed.len()];
+	if let Some(rest) = trimmed.strip_prefix("##") {
+		RustdocLine::Visible(Cow::Owned(format!("{indent}#{rest}")))
+	} else if let Some(rest) = trimmed.strip_prefix("# ") {
+		RustdocLine::Hidden(rest)
+	} else if trimmed == "#" {
+		RustdocLine::Hidden("")
+	} else {
+		RustdocLine::Visible(Cow::Borrowed(line))
+	}
+}
+
+/// The commands typed in a shell session, without their prompts or output, or `None` if there are no prompts.
+#[must_use]
+pub fn session_commands(code: &str) -> Option<String> {
+	let (lines, commands, _) = parse_session(code)?;
+	let mut typed = String::new();
+	for (line, command) in lines.iter().zip(commands.lines()) {
+		if !matches!(line, SessionLine::Output) {
+			typed.push_str(command);
+			typed.push('\n');
+		}
+	}
+	Some(typed)
+}
+
+/// The lines of a Rust code block that readers see, with rustdoc's hidden lines removed.
+#[must_use]
+pub fn visible_rust_lines(code: &str) -> String {
+	let mut visible = String::new();
+	for line in code.lines() {
+		if let RustdocLine::Visible(line) = rustdoc_line(line) {
+			visible.push_str(&line);
+			visible.push('\n');
+		}
+	}
+	visible
+}
+
+/// The complete source of a Rust code block, with rustdoc's hidden lines included.
+#[must_use]
+pub fn all_rust_lines(code: &str) -> String {
+	let mut all = String::new();
+	for line in code.lines() {
+		match rustdoc_line(line) {
+			RustdocLine::Hidden(line) => all.push_str(line),
+			RustdocLine::Visible(line) => all.push_str(&line),
+		}
+		all.push('\n');
+	}
+	all
+}
+
+/// Wrap a snippet the way rustdoc wraps doctests: crate attributes and `extern crate` items stay at the top, and the rest goes inside `fn main` unless the snippet defines its own.
+#[must_use]
+pub fn doctest_source(code: &str) -> String {
+	let mut prelude = String::from("#![allow(unused)]\n");
+	let mut body = String::new();
+	for line in code.lines() {
+		let trimmed = line.trim_start();
+		if trimmed.starts_with("#![") || trimmed.starts_with("extern crate") {
+			prelude.push_str(line);
+			prelude.push('\n');
+		} else {
+			body.push_str(line);
+			body.push('\n');
+		}
+	}
+	if body.contains("fn main") {
+		format!("{prelude}{body}")
+	} else {
+		format!("{prelude}fn main() {{\n{body}}}\n")
+	}
+}
+
+/// A link that opens a Rust snippet on the Rust Playground, e.g. `edition` "2021".
+#[must_use]
+pub fn playground_url(code: &str, edition: &str) -> String {
+	let params = [
+		("version", "stable"),
+		("mode", "debug"),
+		("edition", edition),
+		("code", code),
+	];
+	url::Url::parse_with_params("https://play.rust-lang.org/", params)
+		.unwrap()
+		.into()
+}
+
+fn escape(text: &str) -> String {
+	let mut escaped = String::new();
+	sanitize::push_escaped(&mut escaped, text);
+	escaped
+}
+
+/// Split highlighted html into lines, closing and reopening any spans that cross a line break.
+fn split_lines(html: &str) -> Vec<String> {
+	let mut lines = Vec::new();
+	let mut line = String::new();
+	let mut open_tags: Vec<&str> = Vec::new();
+	let mut rest = html;
+	while let Some(c) = rest.chars().next() {
+		if c == '<' {
+			let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+			let tag = &rest[..end];
+			if tag.starts_with("</") {
+				open_tags.pop();
+			} else {
+				open_tags.push(tag);
+			}
+			line.push_str(tag);
+			rest = &rest[end..];
+		} else if c == '\n' {
+			for _ in &open_tags {
+				line.push_str("</span>");
+			}
+			lines.push(std::mem::take(&mut line));
+			for tag in &open_tags {
+				line.push_str(tag);
+			}
+			rest = &rest[1..];
+		} else {
+			line.push(c);
+			rest = &rest[c.len_utf8()..];
+		}
+	}
+	lines.push(line);
+	lines
+}
+
+/// The same example in each of the languages a [`ui::CodeSelect`] can be made from, as source code.
 pub struct HighlightCodeForLanguage {
 	pub elixir: Cow<'static, str>,
 	pub go: Cow<'static, str>,
@@ -97,6 +542,7 @@ pub struct HighlightCodeForLanguage {
 	pub rust: Cow<'static, str>,
 }
 
+/// The highlighted html of each language's code, for showing it outside a [`ui::CodeSelect`]. Converting the source into a [`ui::CodeSelect`] highlights it already, so this is not needed for that.
 #[cfg(not(target_arch = "wasm32"))]
 #[must_use]
 pub fn highlight_code_for_language(
@@ -113,61 +559,193 @@ pub fn highlight_code_for_language(
 	}
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! highlight_configuration_bash {
+	($i:ident) => {
+		static $i: once_cell::sync::Lazy<
+			Result<tree_sitter_highlight::HighlightConfiguration, String>,
+		> = once_cell::sync::Lazy::new(|| {
+			let language = tree_sitter_bash::language();
+			let query = tree_sitter_bash::HIGHLIGHT_QUERY;
+			let mut config =
+				tree_sitter_highlight::HighlightConfiguration::new(language, "bash", query, "", "")
+					.map_err(|error| error.to_string())?;
+			config.configure(&NAMES);
+			Ok(config)
+		});
+	};
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 macro_rules! highlight_configuration_js {
 	($i:ident) => {
-		static $i: once_cell::sync::Lazy<tree_sitter_highlight::HighlightConfiguration> =
-			once_cell::sync::Lazy::new(|| {
-				let language = tree_sitter_javascript::language();
-				let query = tree_sitter_javascript::HIGHLIGHT_QUERY;
-				let mut config =
-					tree_sitter_highlight::HighlightConfiguration::new(language, query, "", "", "")
-						.unwrap();
-				config.configure(&NAMES);
-				config
-			});
+		static $i: once_cell::sync::Lazy<
+			Result<tree_sitter_highlight::HighlightConfiguration, String>,
+		> = once_cell::sync::Lazy::new(|| {
+			let language = tree_sitter_javascript::language();
+			let query = tree_sitter_javascript::HIGHLIGHT_QUERY;
+			let mut config = tree_sitter_highlight::HighlightConfiguration::new(
+				language,
+				"javascript",
+				query,
+				"",
+				"",
+			)
+			.map_err(|error| error.to_string())?;
+			config.configure(&NAMES);
+			Ok(config)
+		});
 	};
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 macro_rules! highlight_configuration_rust {
 	($i:ident) => {
-		static $i: once_cell::sync::Lazy<tree_sitter_highlight::HighlightConfiguration> =
-			once_cell::sync::Lazy::new(|| {
-				let language = tree_sitter_rust::language();
-				let query = tree_sitter_rust::HIGHLIGHTS_QUERY;
-				let mut config =
-					tree_sitter_highlight::HighlightConfiguration::new(language, query, "", "", "")
-						.unwrap();
-				config.configure(&NAMES);
-				config
-			});
+		static $i: once_cell::sync::Lazy<
+			Result<tree_sitter_highlight::HighlightConfiguration, String>,
+		> = once_cell::sync::Lazy::new(|| {
+			let language = tree_sitter_rust::language();
+			let query = tree_sitter_rust::HIGHLIGHTS_QUERY;
+			let mut config =
+				tree_sitter_highlight::HighlightConfiguration::new(language, "rust", query, "", "")
+					.map_err(|error| error.to_string())?;
+			config.configure(&NAMES);
+			Ok(config)
+		});
 	};
 }
 
+/// The grammars are not built for wasm, so code rendered in the browser is left plain.
 #[cfg(target_arch = "wasm32")]
-pub fn highlight(_code: &str, _language: Language) -> String {
-	unimplemented!()
+#[must_use]
+pub fn highlight(code: &str, _language: Language) -> String {
+	escape(code)
 }
 
+#[cfg(target_arch = "wasm32")]
+fn highlight_inline(code: &str, _language: Language) -> String {
+	escape(code)
+}
+
+/// Inline Rust is often a bare type like `Vec<T>`, which does not parse on its own, so it is highlighted as the right hand side of a type alias when that parses better.
 #[cfg(not(target_arch = "wasm32"))]
+fn highlight_inline(code: &str, language: Language) -> String {
+	let highlighted = highlight(code, language);
+	if language != Language::Rust || highlighted.contains("<span") {
+		return highlighted;
+	}
+	let prefix = "type _ = ";
+	let context = format!("{prefix}{code};");
+	highlight_range(&context, prefix.len()..prefix.len() + code.len(), language)
+}
+
+/// Highlight `code`, falling back to plain escaped code if the grammar fails, which is recorded for [`take_highlight_errors`].
+#[cfg(not(target_arch = "wasm32"))]
+#[must_use]
 pub fn highlight(code: &str, language: Language) -> String {
+	highlight_range(code, 0..code.len(), language)
+}
+
+#[derive(Debug)]
+pub enum HighlightError {
+	Query(Language, String),
+	Highlight(Language, String),
+}
+
+impl std::fmt::Display for HighlightError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			HighlightError::Query(language, error) => {
+				write!(f, "invalid {language:?} highlight query: {error}")
+			}
+			HighlightError::Highlight(language, error) => {
+				write!(f, "failed to highlight {language:?}: {error}")
+			}
+		}
+	}
+}
+
+impl std::error::Error for HighlightError {}
+
+thread_local! {
+	static HIGHLIGHT_ERRORS: std::cell::RefCell<Vec<HighlightError>> =
+		const { std::cell::RefCell::new(Vec::new()) };
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+	static HIGHLIGHTER: std::cell::RefCell<tree_sitter_highlight::Highlighter> =
+		std::cell::RefCell::new(tree_sitter_highlight::Highlighter::new());
+}
+
+/// The code, the range of it highlighted and the language, which together decide the highlighted html.
+#[cfg(not(target_arch = "wasm32"))]
+type HighlightKey = (String, std::ops::Range<usize>, Language);
+
+/// Highlighted code by what was highlighted. Posts are rendered once at startup to fill it, so serving them does no parsing.
+#[cfg(not(target_arch = "wasm32"))]
+static HIGHLIGHT_CACHE: once_cell::sync::Lazy<
+	std::sync::RwLock<std::collections::HashMap<HighlightKey, String>>,
+> = once_cell::sync::Lazy::new(Default::default);
+
+/// The most highlighted snippets cached. Every post fits with room to spare, and code highlighted once the cache is full is highlighted again each time.
+#[cfg(not(target_arch = "wasm32"))]
+const HIGHLIGHT_CACHE_CAPACITY: usize = 4096;
+
+/// Take the errors recorded by highlighting on this thread since they were last taken.
+#[must_use]
+pub fn take_highlight_errors() -> Vec<HighlightError> {
+	HIGHLIGHT_ERRORS.with(|errors| std::mem::take(&mut *errors.borrow_mut()))
+}
+
+/// Highlight only the `range` of `code`, letting the code around it give the grammar the context it needs to parse fragments.
+#[cfg(not(target_arch = "wasm32"))]
+fn highlight_range(code: &str, range: std::ops::Range<usize>, language: Language) -> String {
+	let key = (code.to_owned(), range.clone(), language);
+	if let Some(highlighted) = HIGHLIGHT_CACHE
+		.read()
+		.ok()
+		.and_then(|cache| cache.get(&key).cloned())
+	{
+		return highlighted;
+	}
+	match try_highlight_range(code, range.clone(), language) {
+		Ok(highlighted) => {
+			if let Ok(mut cache) = HIGHLIGHT_CACHE.write() {
+				if cache.len() < HIGHLIGHT_CACHE_CAPACITY {
+					cache.insert(key, highlighted.clone());
+				}
+			}
+			highlighted
+		}
+		Err(error) => {
+			HIGHLIGHT_ERRORS.with(|errors| errors.borrow_mut().push(error));
+			escape(&code[range])
+		}
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn try_highlight_range(
+	code: &str,
+	range: std::ops::Range<usize>,
+	language: Language,
+) -> Result<String, HighlightError> {
 	static NAMES: once_cell::sync::Lazy<Vec<String>> = once_cell::sync::Lazy::new(|| {
-		[
-			"comment",
-			"function",
-			"keyword",
-			"operator",
-			"punctuation",
-			"string",
-			"type",
-			"variable",
-		]
-		.iter()
-		.copied()
-		.map(String::from)
-		.collect()
+		ui::HIGHLIGHT_NAMES
+			.iter()
+			.copied()
+			.map(String::from)
+			.collect()
+	});
+	// A capture like `function.macro` gets the classes `function macro`, so themes can style it as a function or as a macro.
+	static CLASSES: once_cell::sync::Lazy<Vec<String>> = once_cell::sync::Lazy::new(|| {
+		ui::HIGHLIGHT_NAMES
+			.iter()
+			.map(|name| name.replace('.', " "))
+			.collect()
 	});
+	highlight_configuration_bash!(BASH);
 	highlight_configuration_js!(ELIXIR);
 	highlight_configuration_js!(GO);
 	highlight_configuration_js!(JAVASCRIPT);
@@ -176,6 +754,7 @@ pub fn highlight(code: &str, language: Language) -> String {
 	highlight_configuration_js!(RUBY);
 	highlight_configuration_rust!(RUST);
 	let highlight_configuration = match language {
+		Language::Bash => &BASH,
 		Language::Elixir => &ELIXIR,
 		Language::Go => &GO,
 		Language::Javascript => &JAVASCRIPT,
@@ -184,26 +763,46 @@ pub fn highlight(code: &str, language: Language) -> String {
 		Language::Ruby => &RUBY,
 		Language::Rust => &RUST,
 	};
-	let mut highlighter = tree_sitter_highlight::Highlighter::new();
-	let highlights = highlighter
-		.highlight(highlight_configuration, code.as_bytes(), None, |_| None)
-		.unwrap();
-	let mut highlighted_code = String::new();
-	for event in highlights {
-		match event.unwrap() {
-			tree_sitter_highlight::HighlightEvent::Source { start, end } => {
-				highlighted_code.push_str(&code[start..end]);
-			}
-			tree_sitter_highlight::HighlightEvent::HighlightStart(highlight) => {
-				highlighted_code.push_str(&format!(
-					"<span class=\"{}\">",
-					NAMES.get(highlight.0).unwrap()
-				));
-			}
-			tree_sitter_highlight::HighlightEvent::HighlightEnd => {
-				highlighted_code.push_str("</span>");
+	let highlight_configuration = highlight_configuration
+		.as_ref()
+		.map_err(|error| HighlightError::Query(language, error.clone()))?;
+	HIGHLIGHTER.with(|highlighter| {
+		let mut highlighter = highlighter.borrow_mut();
+		let highlights = highlighter
+			.highlight(highlight_configuration, code.as_bytes(), None, |_| None)
+			.map_err(|error| HighlightError::Highlight(language, error.to_string()))?;
+		let mut highlighted_code = String::new();
+		// Spans are only written once they contain source from the range, so none are left empty.
+		let mut open_spans: Vec<(&str, bool)> = Vec::new();
+		for event in highlights {
+			match event.map_err(|error| HighlightError::Highlight(language, error.to_string()))? {
+				tree_sitter_highlight::HighlightEvent::Source { start, end } => {
+					let start = start.max(range.start);
+					let end = end.min(range.end);
+					if start >= end {
+						continue;
+					}
+					for (name, written) in &mut open_spans {
+						if !*written {
+							highlighted_code.push_str("<span class=\"");
+							highlighted_code.push_str(name);
+							highlighted_code.push_str("\">");
+							*written = true;
+						}
+					}
+					highlighted_code.push_str(&escape(&code[start..end]));
+				}
+				tree_sitter_highlight::HighlightEvent::HighlightStart(highlight) => {
+					let class = CLASSES.get(highlight.0).map_or("", String::as_str);
+					open_spans.push((class, false));
+				}
+				tree_sitter_highlight::HighlightEvent::HighlightEnd => {
+					if let Some((_, true)) = open_spans.pop() {
+						highlighted_code.push_str("</span>");
+					}
+				}
 			}
 		}
-	}
-	highlighted_code
+		Ok(highlighted_code)
+	})
 }
@@ -0,0 +1,74 @@
+use crate as ui;
+use pinwheel::prelude::*;
+
+/// The same example in several languages, one tab each. Tabs are radio buttons, so switching between them needs no javascript.
+#[derive(builder, Default, new)]
+#[new(default)]
+pub struct CodeSelect {
+	#[builder]
+	pub id: Option<String>,
+	#[builder]
+	pub tabs: Vec<CodeSelectTab>,
+}
+
+pub struct CodeSelectTab {
+	pub title: String,
+	/// The code with anything shown alongside it, like a copy button.
+	pub code: Node,
+}
+
+impl Component for CodeSelect {
+	fn into_node(self) -> Node {
+		let id = self.id.unwrap_or_else(|| "code-select".to_owned());
+		let tabs = self.tabs.into_iter().enumerate().map(|(index, tab)| {
+			let tab_id = format!("{id}-{index}");
+			let input = input()
+				.class("code-select-input")
+				.attribute("type", "radio")
+				.attribute("name", id.clone())
+				.attribute("id", tab_id.clone())
+				.attribute("checked", if index == 0 { Some("") } else { None });
+			let label = label()
+				.class("code-select-label")
+				.attribute("for", tab_id)
+				.child(tab.title);
+			let code = div().class("code-select-code-wrapper").child(tab.code);
+			fragment().child(input).child(label).child(code)
+		});
+		ui::Card::new()
+			.child(div().class("code-select-grid").children(tabs))
+			.into_node()
+	}
+}
+
+impl From<ui::HighlightCodeForLanguage> for CodeSelect {
+	/// A tab for each language with code, each rendered by [`ui::Code`] so it is highlighted like any other code block.
+	fn from(code_for_language: ui::HighlightCodeForLanguage) -> CodeSelect {
+		let ui::HighlightCodeForLanguage {
+			elixir,
+			go,
+			javascript,
+			php,
+			python,
+			ruby,
+			rust,
+		} = code_for_language;
+		let tabs = [
+			("elixir", elixir, ui::Language::Elixir),
+			("go", go, ui::Language::Go),
+			("javascript", javascript, ui::Language::Javascript),
+			("php", php, ui::Language::Php),
+			("python", python, ui::Language::Python),
+			("ruby", ruby, ui::Language::Ruby),
+			("rust", rust, ui::Language::Rust),
+		]
+		.into_iter()
+		.filter(|(_, code, _)| !code.is_empty())
+		.map(|(title, code, language)| CodeSelectTab {
+			title: title.to_owned(),
+			code: ui::Code::new().code(code).language(language).into_node(),
+		})
+		.collect::<Vec<_>>();
+		CodeSelect::new().tabs(tabs)
+	}
+}
@@ -9,14 +9,33 @@ pub const RED: &str = "#ff453a";
 pub const TEAL: &str = "#4dd0e1";
 pub const YELLOW: &str = "#ffd60a";
 
-pub const TERMINAL_BLACK: &str = "#000000";
-pub const TERMINAL_BLUE: &str = "#0a84ff";
-pub const TERMINAL_CYAN: &str = "#4dd0e1";
-pub const TERMINAL_GREEN: &str = "#30d158";
-pub const TERMINAL_MAGENTA: &str = "#ff375f";
-pub const TERMINAL_RED: &str = "#ff453a";
-pub const TERMINAL_WHITE: &str = "#ffffff";
-pub const TERMINAL_YELLOW: &str = "#ffd60a";
+pub const LIGHT_BLUE: &str = "#0064d1";
+pub const LIGHT_GRAY: &str = "#6e6e73";
+pub const LIGHT_GREEN: &str = "#248a3d";
+pub const LIGHT_INDIGO: &str = "#3634a3";
+pub const LIGHT_ORANGE: &str = "#c93400";
+pub const LIGHT_PINK: &str = "#d30f45";
+pub const LIGHT_PURPLE: &str = "#8944ab";
+pub const LIGHT_RED: &str = "#d70015";
+pub const LIGHT_TEAL: &str = "#0071a4";
+pub const LIGHT_YELLOW: &str = "#b25000";
+
+pub const TERMINAL_BLACK: &str = "var(--terminal-black)";
+pub const TERMINAL_BLUE: &str = "var(--terminal-blue)";
+pub const TERMINAL_BRIGHT_BLACK: &str = "var(--terminal-bright-black)";
+pub const TERMINAL_BRIGHT_BLUE: &str = "var(--terminal-bright-blue)";
+pub const TERMINAL_BRIGHT_CYAN: &str = "var(--terminal-bright-cyan)";
+pub const TERMINAL_BRIGHT_GREEN: &str = "var(--terminal-bright-green)";
+pub const TERMINAL_BRIGHT_MAGENTA: &str = "var(--terminal-bright-magenta)";
+pub const TERMINAL_BRIGHT_RED: &str = "var(--terminal-bright-red)";
+pub const TERMINAL_BRIGHT_WHITE: &str = "var(--terminal-bright-white)";
+pub const TERMINAL_BRIGHT_YELLOW: &str = "var(--terminal-bright-yellow)";
+pub const TERMINAL_CYAN: &str = "var(--terminal-cyan)";
+pub const TERMINAL_GREEN: &str = "var(--terminal-green)";
+pub const TERMINAL_MAGENTA: &str = "var(--terminal-magenta)";
+pub const TERMINAL_RED: &str = "var(--terminal-red)";
+pub const TERMINAL_WHITE: &str = "var(--terminal-white)";
+pub const TERMINAL_YELLOW: &str = "var(--terminal-yellow)";
 
 pub const BACKGROUND: &str = "var(--background-color)";
 pub const BORDER: &str = "var(--border-color)";
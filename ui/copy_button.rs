@@ -0,0 +1,41 @@
+use pinwheel::prelude::*;
+
+/// A button that copies a code block's source to the clipboard. It does nothing until it is hydrated by a client.
+#[derive(new, serde::Serialize, serde::Deserialize)]
+pub struct CopyButton {
+	pub code: String,
+}
+
+impl Component for CopyButton {
+	fn into_node(self) -> Node {
+		let copied = Mutable::new(false);
+		let onclick = {
+			let copied = copied.clone();
+			move |_| {
+				copy(&self.code);
+				copied.set(true);
+			}
+		};
+		let label = copied
+			.signal()
+			.map(|copied| if copied { "Copied" } else { "Copy" });
+		button()
+			.class("code-copy-button")
+			.attribute("type", "button")
+			.attribute("title", "Copy to clipboard")
+			.onclick(onclick)
+			.child_signal(label)
+			.into_node()
+	}
+}
+
+#[cfg(target_arch = "wasm32")]
+fn copy(text: &str) {
+	if let Some(window) = web_sys::window() {
+		// The promise only reports whether the copy succeeded, and there is nothing to do if it failed.
+		let _ = window.navigator().clipboard().write_text(text);
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy(_text: &str) {}
@@ -0,0 +1,24 @@
+use pinwheel::prelude::*;
+
+/// A section collapsed behind its summary until it is opened, which needs no javascript.
+#[derive(builder, children, Default, new)]
+#[new(default)]
+pub struct Details {
+	#[builder]
+	pub open: bool,
+	#[builder]
+	pub summary: Option<String>,
+	pub children: Vec<Node>,
+}
+
+impl Component for Details {
+	fn into_node(self) -> Node {
+		let title = self.summary.unwrap_or_else(|| "Details".to_owned());
+		details()
+			.class("details")
+			.attribute("open", self.open)
+			.child(summary().class("details-summary").child(title))
+			.child(div().class("details-body").child(self.children))
+			.into_node()
+	}
+}
@@ -0,0 +1,105 @@
+//! Links to Rust documentation written like rustdoc's intra-doc links, e.g. ``[`Vec`](std::vec::Vec)`` or ``[`tokio::spawn`]``.
+use pulldown_cmark::{BrokenLink, CowStr};
+
+/// The crates documented on doc.rust-lang.org rather than docs.rs.
+const STD_CRATES: [&str; 5] = ["alloc", "core", "proc_macro", "std", "test"];
+
+/// The kinds of item a path can be disambiguated with, like `struct@Vec`, and the prefix of each kind's page.
+const DISAMBIGUATORS: [(&str, &str); 17] = [
+	("attr", "attr"),
+	("const", "constant"),
+	("constant", "constant"),
+	("derive", "derive"),
+	("enum", "enum"),
+	("fn", "fn"),
+	("function", "fn"),
+	("macro", "macro"),
+	("mod", "mod"),
+	("module", "mod"),
+	("primitive", "primitive"),
+	("static", "static"),
+	("struct", "struct"),
+	("trait", "trait"),
+	("type", "type"),
+	("tyalias", "type"),
+	("union", "union"),
+];
+
+/// The documentation url for a Rust path, or `None` if `path` does not look like one.
+///
+/// Paths must name their crate, since there is no scope to resolve them in. A path ending in `!` is a macro and one ending in `()` is a function. Any other kind of item can be given with a disambiguator like `trait@std::io::Read`, and without one the link searches the crate's documentation and goes to the first result, because the kind of item decides the url.
+pub(crate) fn doc_url(path: &str) -> Option<String> {
+	let path = path.trim();
+	let (kind, path) = match path.split_once('@') {
+		Some((disambiguator, path)) => {
+			let (_, kind) = DISAMBIGUATORS
+				.iter()
+				.find(|(name, _)| *name == disambiguator)?;
+			(Some(*kind), path)
+		}
+		None => {
+			if let Some(path) = path.strip_suffix('!') {
+				(Some("macro"), path)
+			} else if let Some(path) = path.strip_suffix("()") {
+				(Some("fn"), path)
+			} else {
+				(None, path)
+			}
+		}
+	};
+	if kind == Some("primitive") {
+		return is_identifier(path)
+			.then(|| format!("https://doc.rust-lang.org/std/primitive.{path}.html"));
+	}
+	let segments = path.split("::").collect::<Vec<_>>();
+	if !segments.iter().all(|segment| is_identifier(segment)) {
+		return None;
+	}
+	let (name, modules) = segments.split_last()?;
+	let Some(krate) = modules.first() else {
+		// A single name is only a crate when it says so.
+		return (kind == Some("mod")).then(|| crate_url(name));
+	};
+	let url = crate_url(krate);
+	let modules = modules[1..]
+		.iter()
+		.flat_map(|module| [*module, "/"])
+		.collect::<String>();
+	let url = match kind {
+		Some("mod") => format!("{url}{modules}{name}/index.html"),
+		Some(kind) => format!("{url}{modules}{kind}.{name}.html"),
+		None => format!("{url}?search={path}&go_to_first=true"),
+	};
+	Some(url)
+}
+
+/// Resolve a reference link with no definition, like ``[`tokio::spawn`]``, whose label is a path with its crate.
+pub(crate) fn resolve_broken_link(link: BrokenLink) -> Option<(CowStr, CowStr)> {
+	let reference = link.reference;
+	let reference = reference.trim();
+	let path = reference
+		.strip_prefix('`')
+		.and_then(|reference| reference.strip_suffix('`'))
+		.unwrap_or(reference);
+	if !path.contains("::") && !path.contains('@') {
+		return None;
+	}
+	let url = doc_url(path)?;
+	Some((url.into(), "".into()))
+}
+
+fn crate_url(krate: &str) -> String {
+	if STD_CRATES.contains(&krate) {
+		format!("https://doc.rust-lang.org/{krate}/")
+	} else {
+		format!("https://docs.rs/{krate}/latest/{krate}/")
+	}
+}
+
+fn is_identifier(name: &str) -> bool {
+	let mut chars = name.chars();
+	chars
+		.next()
+		.is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
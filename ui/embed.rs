@@ -0,0 +1,113 @@
+use crate as ui;
+use pinwheel::prelude::*;
+
+/// A video from youtube-nocookie.com, shown as its thumbnail until it is clicked. Thumbnails are served by the site from `/youtube/{id}.jpg`, so nothing is loaded from a third party on a page view, and a video without one shows a placeholder instead. Without a client the thumbnail links to the player.
+#[derive(new, serde::Serialize, serde::Deserialize)]
+pub struct YouTubeEmbed {
+	pub id: String,
+}
+
+impl Component for YouTubeEmbed {
+	fn into_node(self) -> Node {
+		let playing = Mutable::new(false);
+		let src = format!("https://www.youtube-nocookie.com/embed/{}", self.id);
+		let thumbnail = format!("/youtube/{}.jpg", self.id);
+		let content = playing.signal().map(move |is_playing| {
+			if is_playing {
+				iframe()
+					.class("embed-video-iframe")
+					.attribute("allow", "autoplay; encrypted-media; picture-in-picture")
+					.attribute("allowfullscreen", true)
+					.attribute("src", format!("{src}?autoplay=1"))
+					.attribute("title", "YouTube video")
+					.into_node()
+			} else {
+				let playing = playing.clone();
+				a().class("embed-video-facade")
+					.attribute("href", src.clone())
+					.attribute("title", "Play the YouTube video")
+					.onclick(move |event| {
+						event.prevent_default();
+						playing.set(true);
+					})
+					.child(span().class("embed-video-label").child("YouTube video"))
+					// A missing thumbnail has no alt text to show, so the label behind it shows through.
+					.child(
+						img()
+							.class("embed-video-thumbnail")
+							.attribute("alt", "")
+							.attribute("loading", "lazy")
+							.attribute("src", thumbnail.clone()),
+					)
+					.child(
+						span()
+							.class("embed-video-play")
+							.attribute("aria-hidden", "true"),
+					)
+					.into_node()
+			}
+		});
+		div().class("embed-video").child_signal(content).into_node()
+	}
+}
+
+/// A video file served alongside a post, played by the browser.
+#[derive(builder, new)]
+pub struct Video {
+	pub src: String,
+	/// A description of the video, read by screen readers and shown if the browser cannot play it.
+	#[builder]
+	#[new(default)]
+	pub title: Option<String>,
+}
+
+impl Component for Video {
+	fn into_node(self) -> Node {
+		let fallback = a().attribute("href", self.src.clone()).child(
+			self.title
+				.clone()
+				.unwrap_or_else(|| "Download the video".to_owned()),
+		);
+		video()
+			.class("embed-video-file")
+			.attribute("aria-label", self.title)
+			.attribute("controls", true)
+			.attribute("preload", "metadata")
+			.attribute("src", self.src)
+			.child(fallback)
+			.into_node()
+	}
+}
+
+/// A link to another page shown as a card with its title and site.
+#[derive(builder, Default, new)]
+#[new(default)]
+pub struct LinkCard {
+	#[builder]
+	pub href: Option<String>,
+	#[builder]
+	pub site: Option<String>,
+	#[builder]
+	pub title: Option<String>,
+}
+
+impl Component for LinkCard {
+	fn into_node(self) -> Node {
+		let site = self
+			.site
+			.map(|site| div().class("link-card-site").child(site));
+		div()
+			.class("link-card")
+			.child(
+				ui::Card::new()
+					.child(
+						ui::Link::new()
+							.class("link-card-title".to_owned())
+							.href(self.href)
+							.child(self.title),
+					)
+					.child(site),
+			)
+			.into_node()
+	}
+}
@@ -0,0 +1,126 @@
+use crate as ui;
+use std::ops::RangeInclusive;
+
+/// The attributes of a fenced code block, parsed from its info string.
+///
+/// The first token names the language and the rest are flags or `key=value` pairs separated by commas or whitespace, with values quoted if they contain either, e.g. ```` ```rust,linenos,hl_lines="2-4 7",title=main.rs ````.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FenceInfo {
+	pub language: Option<String>,
+	pub line_numbers: bool,
+	pub highlight_lines: Vec<RangeInclusive<usize>>,
+	pub title: Option<String>,
+	pub flags: Vec<String>,
+}
+
+impl FenceInfo {
+	#[must_use]
+	pub fn parse(info: &str) -> FenceInfo {
+		let mut fence_info = FenceInfo::default();
+		for (index, token) in tokenize(info).into_iter().enumerate() {
+			match token.split_once('=') {
+				Some((key, value)) => {
+					let value = value.trim_matches('"');
+					match key {
+						"hl_lines" | "highlight" => {
+							fence_info.highlight_lines = parse_line_ranges(value);
+						}
+						"title" | "file" => fence_info.title = Some(value.to_owned()),
+						_ => fence_info.flags.push(token),
+					}
+				}
+				None if index == 0 => fence_info.language = Some(token),
+				None => match token.as_str() {
+					"linenos" | "line_numbers" => fence_info.line_numbers = true,
+					_ => fence_info.flags.push(token),
+				},
+			}
+		}
+		fence_info
+	}
+
+	/// The grammar to highlight with, looking through the `diff-` prefix of fences like `diff-rust`. Commands in `console` sessions are highlighted as bash.
+	#[must_use]
+	pub fn highlight_language(&self) -> Option<ui::Language> {
+		let language = match self.language.as_deref()? {
+			"console" => "bash",
+			language => language.strip_prefix("diff-").unwrap_or(language),
+		};
+		ui::Language::from_name(language)
+	}
+
+	/// The Rust edition selected by a rustdoc-style `edition2018` flag, defaulting to 2021.
+	#[must_use]
+	pub fn edition(&self) -> &str {
+		self.flags
+			.iter()
+			.find_map(|flag| flag.strip_prefix("edition"))
+			.unwrap_or("2021")
+	}
+
+	#[must_use]
+	pub fn is_ansi(&self) -> bool {
+		matches!(self.language.as_deref(), Some("ansi" | "console"))
+	}
+
+	#[must_use]
+	pub fn is_shell_session(&self) -> bool {
+		matches!(
+			self.language.as_deref(),
+			Some("bash" | "console" | "sh" | "shell")
+		)
+	}
+
+	#[must_use]
+	pub fn is_diff(&self) -> bool {
+		self.language
+			.as_deref()
+			.is_some_and(|language| language == "diff" || language.starts_with("diff-"))
+	}
+
+	#[must_use]
+	pub fn has_flag(&self, flag: &str) -> bool {
+		self.flags.iter().any(|f| f == flag)
+	}
+}
+
+/// Split an info string on commas and whitespace, keeping quoted values together.
+fn tokenize(info: &str) -> Vec<String> {
+	let mut tokens = Vec::new();
+	let mut token = String::new();
+	let mut quoted = false;
+	for c in info.chars() {
+		match c {
+			'"' => {
+				quoted = !quoted;
+				token.push(c);
+			}
+			',' | ' ' | '\t' if !quoted => {
+				if !token.is_empty() {
+					tokens.push(std::mem::take(&mut token));
+				}
+			}
+			_ => token.push(c),
+		}
+	}
+	if !token.is_empty() {
+		tokens.push(token);
+	}
+	tokens
+}
+
+/// Parse line ranges like `2-4 7`, ignoring anything that is not a number or range. A range written backwards, like `4-2`, covers the same lines as `2-4`.
+fn parse_line_ranges(value: &str) -> Vec<RangeInclusive<usize>> {
+	value
+		.split([' ', ';'])
+		.filter_map(|range| {
+			if let Some((start, end)) = range.split_once('-') {
+				let (start, end): (usize, usize) = (start.parse().ok()?, end.parse().ok()?);
+				Some(start.min(end)..=start.max(end))
+			} else {
+				let line = range.parse().ok()?;
+				Some(line..=line)
+			}
+		})
+		.collect()
+}
@@ -0,0 +1,188 @@
+use crate::colors;
+
+/// The tree-sitter capture names recognized when highlighting. A capture like `function.macro` is rendered with the classes `function macro`, so a theme can style `function` and refine `function.macro`.
+pub const HIGHLIGHT_NAMES: &[&str] = &[
+	"attribute",
+	"comment",
+	"comment.documentation",
+	"constant",
+	"constant.builtin",
+	"constructor",
+	"embedded",
+	"escape",
+	"function",
+	"function.builtin",
+	"function.macro",
+	"function.method",
+	"keyword",
+	"label",
+	"number",
+	"operator",
+	"property",
+	"punctuation",
+	"punctuation.bracket",
+	"punctuation.delimiter",
+	"punctuation.special",
+	"string",
+	"string.special",
+	"tag",
+	"type",
+	"type.builtin",
+	"variable",
+	"variable.builtin",
+	"variable.parameter",
+];
+
+/// A set of styles for highlight names.
+pub struct HighlightTheme {
+	pub name: &'static str,
+	pub styles: &'static [(&'static str, HighlightStyle)],
+}
+
+#[derive(Clone, Copy)]
+pub struct HighlightStyle {
+	pub color: &'static str,
+	pub bold: bool,
+	pub italic: bool,
+}
+
+/// The themes used when the site is shown in its light and dark colour schemes.
+#[derive(Clone, Copy)]
+pub struct HighlightThemes {
+	pub light: &'static HighlightTheme,
+	pub dark: &'static HighlightTheme,
+}
+
+impl Default for HighlightThemes {
+	fn default() -> Self {
+		HighlightThemes {
+			light: &LIGHT,
+			dark: &DARK,
+		}
+	}
+}
+
+impl HighlightThemes {
+	/// The stylesheet for both themes, switching with `prefers-color-scheme` like the rest of the site.
+	#[must_use]
+	pub fn css(&self) -> String {
+		let mut css = self.light.css();
+		css.push_str("@media (prefers-color-scheme: dark) {\n");
+		css.push_str(&self.dark.css());
+		css.push_str("}\n");
+		css
+	}
+}
+
+impl HighlightTheme {
+	#[must_use]
+	pub fn css(&self) -> String {
+		let mut css = String::new();
+		for (name, style) in self.styles {
+			// Highlight names are common words, so they only apply inside code.
+			let classes = name
+				.split('.')
+				.fold(String::new(), |classes, class| classes + "." + class);
+			css.push_str(
+				&[
+					".code ",
+					&classes,
+					", .inline-code ",
+					&classes,
+					" { color: ",
+					style.color,
+					";",
+				]
+				.concat(),
+			);
+			if style.bold {
+				css.push_str(" font-weight: bold;");
+			}
+			if style.italic {
+				css.push_str(" font-style: italic;");
+			}
+			css.push_str(" }\n");
+		}
+		css
+	}
+}
+
+const fn plain(color: &'static str) -> HighlightStyle {
+	HighlightStyle {
+		color,
+		bold: false,
+		italic: false,
+	}
+}
+
+const fn italic(color: &'static str) -> HighlightStyle {
+	HighlightStyle {
+		color,
+		bold: false,
+		italic: true,
+	}
+}
+
+const fn bold(color: &'static str) -> HighlightStyle {
+	HighlightStyle {
+		color,
+		bold: true,
+		italic: false,
+	}
+}
+
+pub static LIGHT: HighlightTheme = HighlightTheme {
+	name: "light",
+	styles: &[
+		("attribute", plain(colors::LIGHT_PURPLE)),
+		("comment", italic(colors::LIGHT_GRAY)),
+		("constant", plain(colors::LIGHT_YELLOW)),
+		("constant.builtin", plain(colors::LIGHT_YELLOW)),
+		("constructor", plain(colors::LIGHT_TEAL)),
+		("embedded", plain(colors::TEXT)),
+		("escape", bold(colors::LIGHT_PINK)),
+		("function", plain(colors::LIGHT_BLUE)),
+		("function.macro", plain(colors::LIGHT_INDIGO)),
+		("keyword", plain(colors::LIGHT_PURPLE)),
+		("label", italic(colors::LIGHT_PINK)),
+		("number", plain(colors::LIGHT_YELLOW)),
+		("operator", plain(colors::LIGHT_GRAY)),
+		("property", plain(colors::LIGHT_TEAL)),
+		("punctuation", plain(colors::LIGHT_GRAY)),
+		("string", plain(colors::LIGHT_GREEN)),
+		("string.special", plain(colors::LIGHT_PINK)),
+		("tag", plain(colors::LIGHT_RED)),
+		("type", plain(colors::LIGHT_ORANGE)),
+		("variable", plain(colors::LIGHT_TEAL)),
+		("variable.builtin", plain(colors::LIGHT_PURPLE)),
+		("variable.parameter", plain(colors::LIGHT_TEAL)),
+	],
+};
+
+pub static DARK: HighlightTheme = HighlightTheme {
+	name: "dark",
+	styles: &[
+		("attribute", plain(colors::PURPLE)),
+		("comment", italic(colors::GRAY)),
+		("constant", plain(colors::YELLOW)),
+		("constant.builtin", plain(colors::YELLOW)),
+		("constructor", plain(colors::TEAL)),
+		("embedded", plain(colors::TEXT)),
+		("escape", bold(colors::PINK)),
+		("function", plain(colors::BLUE)),
+		("function.macro", plain(colors::INDIGO)),
+		("keyword", plain(colors::PURPLE)),
+		("label", italic(colors::PINK)),
+		("number", plain(colors::YELLOW)),
+		("operator", plain(colors::GRAY)),
+		("property", plain(colors::TEAL)),
+		("punctuation", plain(colors::GRAY)),
+		("string", plain(colors::GREEN)),
+		("string.special", plain(colors::PINK)),
+		("tag", plain(colors::RED)),
+		("type", plain(colors::ORANGE)),
+		("variable", plain(colors::TEAL)),
+		("variable.builtin", plain(colors::PURPLE)),
+		("variable.parameter", plain(colors::TEAL)),
+	],
+};
@@ -5,13 +5,16 @@ use pinwheel::prelude::*;
 pub struct Img {
 	#[builder]
 	pub alt: Option<String>,
+	/// A caption shown below the image, rendering it as a figure.
+	#[builder]
+	pub caption: Option<String>,
 	#[builder]
 	pub src: Option<String>,
 }
 
 impl Component for Img {
 	fn into_node(self) -> Node {
-		details()
+		let details = details()
 			.class("image-details")
 			.child(
 				summary().class("image-details-summary").child(
@@ -28,7 +31,15 @@ impl Component for Img {
 						.attribute("alt", self.alt)
 						.attribute("src", self.src),
 				),
-			)
-			.into_node()
+			);
+		if let Some(caption) = self.caption {
+			figure()
+				.class("image-figure")
+				.child(details)
+				.child(figcaption().class("image-figure-caption").child(caption))
+				.into_node()
+		} else {
+			details.into_node()
+		}
 	}
 }
@@ -1,9 +1,27 @@
-pub use self::{card::*, code::*, image::*, link::*, markdown::*, topbar::*};
+pub use self::{
+	ansi::*, callout::*, card::*, code::*, code_select::*, copy_button::*, details::*, embed::*,
+	fence::*, highlight_theme::*, image::*, link::*, markdown::*, sanitize::*, shortcode::*,
+	topbar::*,
+};
 
+mod ansi;
+mod callout;
 mod card;
 mod code;
+mod code_select;
 pub mod colors;
+mod copy_button;
+mod details;
+mod doc_link;
+mod embed;
+mod fence;
+mod highlight_theme;
 mod image;
 mod link;
+mod liquid;
 mod markdown;
+mod markers;
+mod math;
+mod sanitize;
+mod shortcode;
 mod topbar;
@@ -0,0 +1,140 @@
+//! DEV.to liquid tags like `{% youtube X5KmB8Laemg %}`, left in posts imported from DEV.to.
+use crate::{self as ui, markdown::Counters, markers};
+use pinwheel::prelude::*;
+use std::ops::Range;
+
+/// A liquid tag found in markdown, outside code.
+pub(crate) struct LiquidTag {
+	/// The range of the tag in the markdown, including any `{# … #}` it was commented out with on import.
+	pub range: Range<usize>,
+	pub name: String,
+	pub argument: String,
+}
+
+/// Find the liquid tags in `markdown` outside the `skip` ranges, which cover code so template syntax in examples is left as written.
+pub(crate) fn find_liquid_tags(markdown: &str, skip: &[Range<usize>]) -> Vec<LiquidTag> {
+	let mut tags = Vec::new();
+	let mut search_start = 0;
+	while let Some(index) = markdown[search_start..].find("{%") {
+		let start = search_start + index;
+		let Some(len) = markdown[start + 2..].find("%}") else {
+			break;
+		};
+		let end = start + 2 + len + 2;
+		search_start = end;
+		if skip.iter().any(|range| range.contains(&start)) {
+			continue;
+		}
+		let contents = &markdown[start + 2..end - 2];
+		if contents.contains('\n') {
+			continue;
+		}
+		let mut words = contents.split_whitespace();
+		let Some(name) = words.next() else {
+			continue;
+		};
+		let argument = words.collect::<Vec<_>>().join(" ");
+		// Tags were commented out with Tera's `{# … #}` on import, sometimes closed with `%}` by mistake.
+		let mut range = start..end;
+		if let Some(before) = markdown[..start].trim_end_matches(' ').strip_suffix("{#") {
+			let after = markdown[end..].trim_start_matches(' ');
+			if let Some(after) = after
+				.strip_prefix("#}")
+				.or_else(|| after.strip_prefix("%}"))
+			{
+				range = before.len()..markdown.len() - after.len();
+				search_start = range.end;
+			}
+		}
+		tags.push(LiquidTag {
+			range,
+			name: name.to_owned(),
+			argument,
+		});
+	}
+	tags
+}
+
+/// The marker for a liquid tag, or `None` if the tag is not supported.
+pub(crate) fn liquid_marker(tag: &LiquidTag) -> Option<String> {
+	if !matches!(
+		tag.name.as_str(),
+		"devcomment" | "link" | "post" | "youtube"
+	) {
+		return None;
+	}
+	Some(markers::marker(
+		"liquid",
+		&format!("{} {}", tag.name, tag.argument),
+	))
+}
+
+/// Render the liquid tag in a marker's data, as a block on its own line or inline in a paragraph.
+pub(crate) fn render_liquid_tag(data: &str, block: bool, counters: &mut Counters) -> Option<Node> {
+	let (name, argument) = data.split_once(' ')?;
+	let (href, title) = match name {
+		"youtube" => {
+			let id = argument.split_whitespace().next().filter(|id| is_id(id))?;
+			let embed = Dehydrate::new(
+				format!("video-embed-{}", counters.video_embeds),
+				ui::YouTubeEmbed::new(id.to_owned()),
+			);
+			counters.video_embeds += 1;
+			return Some(embed.into_node());
+		}
+		"devcomment" if is_id(argument) => (
+			format!("https://dev.to/comment/{argument}"),
+			"A comment on DEV".to_owned(),
+		),
+		"link" | "post" => {
+			let href = if argument.starts_with("https://") {
+				argument.to_owned()
+			} else {
+				format!("https://dev.to/{argument}")
+			};
+			(href, post_title(argument))
+		}
+		_ => return None,
+	};
+	let node = if block {
+		ui::LinkCard::new()
+			.href(href)
+			.site("DEV".to_owned())
+			.title(title)
+			.into_node()
+	} else {
+		ui::Link::new().href(href).child(title).into_node()
+	};
+	Some(node)
+}
+
+fn is_id(id: &str) -> bool {
+	!id.is_empty()
+		&& id
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+}
+
+/// A readable title from the slug of a DEV post, like `rust--reasonml---a-beginners-love-story-45a2`, without its trailing id.
+fn post_title(path: &str) -> String {
+	let slug = path
+		.trim_end_matches('/')
+		.rsplit('/')
+		.next()
+		.unwrap_or(path);
+	let slug = match slug.rsplit_once('-') {
+		Some((title, id)) if id.len() <= 4 && id.chars().all(|c| c.is_ascii_alphanumeric()) => {
+			title
+		}
+		_ => slug,
+	};
+	let title = slug
+		.split('-')
+		.filter(|word| !word.is_empty())
+		.collect::<Vec<_>>()
+		.join(" ");
+	let mut chars = title.chars();
+	chars.next().map_or_else(String::new, |first| {
+		first.to_uppercase().chain(chars).collect()
+	})
+}
@@ -1,286 +1,1065 @@
-use crate as ui;
+use crate::{self as ui, doc_link, liquid, markers, math, sanitize, shortcode};
 use convert_case::Casing;
 use pinwheel::prelude::*;
-use pulldown_cmark::{escape::escape_html, Alignment, CodeBlockKind, Event, Options, Parser, Tag};
-use std::{borrow::Cow, fmt::Write};
+use pulldown_cmark::{
+	escape::escape_href, Alignment, CodeBlockKind, CowStr, Event, Options, Parser, Tag,
+};
+use std::{
+	borrow::Cow,
+	collections::{HashMap, HashSet},
+	fmt::Write,
+	ops::Range,
+};
 
-#[derive(builder, new, PartialEq, Eq)]
+/// Ends the excerpt of a document when it is on a line of its own.
+const MORE_MARKER: &str = "<!-- more -->";
+
+#[derive(builder, new, Clone, PartialEq, Eq)]
 pub struct Markdown {
 	string: Cow<'static, str>,
+	#[builder]
+	#[new(default)]
+	html_policy: ui::HtmlPolicy,
+	#[builder]
+	#[new(default)]
+	shortcodes: ui::Shortcodes,
+	#[builder]
+	#[new(default)]
+	typography: Typography,
+	/// Code blocks with more lines than this are folded behind a toggle, unless their fence has the `nofold` flag.
+	#[builder]
+	#[new(default)]
+	code_fold_lines: Option<usize>,
 }
 
-enum State {
-	Ground,
-	Code {
-		code: Option<String>,
-		language: Option<ui::Language>,
-	},
-	Table {
-		part: TablePart,
-		alignments: Vec<Alignment>,
-		column_index: usize,
-	},
-	Heading {
-		heading: Option<String>,
-	},
+/// How the text of a document is typeset. Code is always rendered as written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Typography {
+	/// Curly quotes, en and em dashes and ellipses in place of `"`, `--`, `---` and `...`.
+	pub smart_punctuation: bool,
+	/// Emoji in place of shortcodes like `:tada:`.
+	pub emoji_shortcodes: bool,
 }
 
-enum TablePart {
-	Head,
-	Body,
+impl Default for Typography {
+	fn default() -> Self {
+		Typography {
+			smart_punctuation: true,
+			emoji_shortcodes: false,
+		}
+	}
 }
 
-impl Component for Markdown {
-	#[allow(clippy::too_many_lines)]
-	fn into_node(self) -> Node {
-		let markdown = div().class("markdown");
-		let mut state = State::Ground;
-		let parser = Parser::new_ext(&self.string, Options::all());
-		let mut html = String::new();
+/// A code block in a markdown document, as written by its author.
+pub struct CodeBlock {
+	pub info: ui::FenceInfo,
+	pub code: String,
+	/// The line of the document the block's opening fence is on, starting from 1.
+	pub line: usize,
+}
+
+/// A markdown document rendered to html, with anything that had to be rendered differently than its author probably intended.
+#[derive(Clone)]
+pub struct RenderedMarkdown {
+	pub html: String,
+	pub warnings: Vec<MarkdownWarning>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownWarning {
+	/// The line of the document the problem is on, starting from 1.
+	pub line: usize,
+	/// The byte offset into the document the problem starts at.
+	pub offset: usize,
+	pub message: String,
+	pub kind: MarkdownWarningKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkdownWarningKind {
+	/// The document is written in a way that cannot be rendered as its author probably intended.
+	Markup,
+	/// A grammar failed to highlight code, which is a fault of the site rather than of the document.
+	Highlight,
+}
+
+impl std::fmt::Display for MarkdownWarning {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "line {}: {}", self.line, self.message)
+	}
+}
+
+impl Markdown {
+	#[must_use]
+	pub fn code_blocks(&self) -> Vec<CodeBlock> {
+		let mut code_blocks = Vec::new();
+		let mut current: Option<CodeBlock> = None;
+		for (event, range) in Parser::new_ext(&self.string, self.options()).into_offset_iter() {
+			match event {
+				Event::Start(Tag::CodeBlock(kind)) => {
+					let info = match kind {
+						CodeBlockKind::Indented => ui::FenceInfo::default(),
+						CodeBlockKind::Fenced(info) => ui::FenceInfo::parse(&info),
+					};
+					let line = self.string[..range.start].matches('\n').count() + 1;
+					current = Some(CodeBlock {
+						info,
+						code: String::new(),
+						line,
+					});
+				}
+				Event::Text(text) => {
+					if let Some(current) = &mut current {
+						current.code.push_str(&text);
+					}
+				}
+				Event::End(Tag::CodeBlock(_)) => {
+					code_blocks.extend(current.take());
+				}
+				_ => {}
+			}
+		}
+		code_blocks
+	}
+
+	/// The start of the document, to show in a list of documents: everything before a `<!-- more -->` line, or else its first paragraph with any text in it.
+	///
+	/// The link reference definitions of the whole document and the definitions of the footnotes referenced in the excerpt are kept, wherever they are written.
+	#[must_use]
+	pub fn excerpt(&self) -> Markdown {
+		let mut depth = 0;
+		let mut more = None;
+		let mut paragraphs = Vec::new();
+		let mut footnote_references = Vec::new();
+		let mut footnote_definitions = Vec::new();
+		let mut parser = Parser::new_ext(&self.string, self.options()).into_offset_iter();
+		for (event, range) in parser.by_ref() {
+			match event {
+				Event::Html(raw) if raw.trim() == MORE_MARKER && more.is_none() => {
+					more = Some(0..range.start);
+				}
+				Event::FootnoteReference(label) => footnote_references.push((label, range.start)),
+				Event::Start(Tag::Paragraph) => {
+					if depth == 0 {
+						paragraphs.push(range);
+					}
+				}
+				Event::Start(tag) => {
+					if let Tag::FootnoteDefinition(label) = tag {
+						footnote_definitions.push((label, range));
+					}
+					depth += 1;
+				}
+				Event::End(tag) if !matches!(tag, Tag::Paragraph) => depth -= 1,
+				_ => {}
+			}
+		}
+		let mut reference_definitions = parser
+			.reference_definitions()
+			.iter()
+			.map(|(_, definition)| definition.span.clone())
+			.collect::<Vec<_>>();
+		reference_definitions.sort_by_key(|span| span.start);
+		let excerpt = |range: Range<usize>| {
+			let mut string = self.string[range.clone()].to_owned();
+			let outside = |span: &Range<usize>| span.start < range.start || span.end > range.end;
+			let referenced = |label: &CowStr| {
+				footnote_references
+					.iter()
+					.any(|(reference, offset)| reference == label && range.contains(offset))
+			};
+			let definitions = reference_definitions.iter().chain(
+				footnote_definitions
+					.iter()
+					.filter(|(label, _)| referenced(label))
+					.map(|(_, span)| span),
+			);
+			for span in definitions.filter(|span| outside(span)) {
+				string.push_str("\n\n");
+				string.push_str(self.string[span.clone()].trim_end());
+			}
+			Markdown {
+				string: Cow::Owned(string),
+				..self.clone()
+			}
+		};
+		if let Some(range) = more {
+			return excerpt(range);
+		}
+		paragraphs
+			.into_iter()
+			.map(excerpt)
+			.find(|paragraph| !paragraph.plain_text().is_empty())
+			.unwrap_or_else(|| excerpt(0..0))
+	}
+
+	/// The text of the document without its markup, for a description of it. Code blocks, images and html are left out, and whitespace is collapsed to single spaces.
+	#[must_use]
+	pub fn plain_text(&self) -> String {
+		let marked = markers::mark(&self.string, self.options());
+		let mut text = String::new();
+		let mut skipping = 0;
+		let mut resolve_doc_link = doc_link::resolve_broken_link;
+		let parser = Parser::new_with_broken_link_callback(
+			&marked.markdown,
+			self.options(),
+			Some(&mut resolve_doc_link),
+		);
 		for event in parser {
+			match event {
+				Event::Start(Tag::CodeBlock(_) | Tag::Image(..) | Tag::FootnoteDefinition(_)) => {
+					skipping += 1;
+				}
+				Event::End(Tag::CodeBlock(_) | Tag::Image(..) | Tag::FootnoteDefinition(_)) => {
+					skipping -= 1;
+				}
+				Event::Text(_) | Event::Code(_) if skipping > 0 => {}
+				Event::Text(content) if self.typography.emoji_shortcodes => {
+					text.push_str(&replace_emoji_shortcodes(&content));
+				}
+				Event::Text(content) | Event::Code(content) => text.push_str(&content),
+				Event::SoftBreak
+				| Event::HardBreak
+				| Event::End(
+					Tag::Paragraph
+					| Tag::Heading(..)
+					| Tag::BlockQuote
+					| Tag::Item
+					| Tag::TableCell,
+				) => text.push(' '),
+				_ => {}
+			}
+		}
+		text.split_whitespace().collect::<Vec<_>>().join(" ")
+	}
+
+	/// Render the document to html. This never fails: whatever cannot be rendered as written is rendered as well as it can be and reported in the warnings.
+	#[must_use]
+	pub fn render(&self) -> RenderedMarkdown {
+		self.render_with(&mut Counters::default())
+	}
+
+	/// Render documents shown together on one page, numbering their code blocks, code groups and embeds across all of them so their ids are unique on the page.
+	#[must_use]
+	pub fn render_all(documents: &[Markdown]) -> Vec<RenderedMarkdown> {
+		let mut counters = Counters::default();
+		documents
+			.iter()
+			.map(|document| document.render_with(&mut counters))
+			.collect()
+	}
+
+	#[allow(clippy::too_many_lines)]
+	fn render_with(&self, counters: &mut Counters) -> RenderedMarkdown {
+		// Liquid tags, math and shortcodes are replaced with markers before parsing, so they are found wherever they are written outside code.
+		let marked = markers::mark(&self.string, self.options());
+		let mut renderer = Renderer {
+			source: &self.string,
+			marked: &marked,
+			html: String::new(),
+			warnings: Vec::new(),
+		};
+		for (offset, message) in &marked.warnings {
+			renderer.warn_original(*offset, message);
+		}
+		let mut code: Option<(String, ui::FenceInfo)> = None;
+		let mut table: Option<Table> = None;
+		let mut heading: Option<Heading> = None;
+		// Images open in the alt text of another image, like `![a ![b](b.png)](a.png)`, are nested.
+		let mut images: Vec<Image> = Vec::new();
+		// The open blockquotes, with the kind of each that is a callout and where its body starts in the html.
+		let mut blockquotes: Vec<Option<(ui::CalloutKind, usize)>> = Vec::new();
+		// Consecutive code blocks between `<!-- code-group -->` and `<!-- /code-group -->` are rendered as tabs.
+		let mut code_group: Option<(Vec<ui::CodeSelectTab>, usize)> = None;
+		// Footnotes are numbered in the order they are first referred to or defined.
+		let mut footnotes: HashMap<String, usize> = HashMap::new();
+		let mut footnote_references = Vec::new();
+		let mut defined_footnotes = HashSet::new();
+		let mut sanitizer = ui::Sanitizer::default();
+		// Reference links to Rust paths like [`tokio::spawn`] link to their documentation.
+		let mut resolve_doc_link = doc_link::resolve_broken_link;
+		let mut parser = Parser::new_with_broken_link_callback(
+			&marked.markdown,
+			self.options(),
+			Some(&mut resolve_doc_link),
+		)
+		.into_offset_iter()
+		.peekable();
+		while let Some((event, range)) = parser.next() {
+			// Anything but code in a code group ends it, so it is rendered where it was written.
+			if code_group.is_some() && code.is_none() && !belongs_in_code_group(&event) {
+				renderer.warn(&range, "code group ended by something other than code");
+				if let Some((tabs, _)) = code_group.take() {
+					renderer.push_code_group(tabs, counters.code_groups);
+					counters.code_groups += 1;
+					renderer.warn_highlight_errors(&range);
+				}
+			}
 			match event {
 				Event::Start(tag) => match tag {
 					Tag::Paragraph => {
-						html.push_str("<p>");
+						renderer.html.push_str("<p>");
 					}
 					Tag::Heading(_, _, _) => {
-						state = State::Heading { heading: None };
+						heading = Some(Heading {
+							start: renderer.html.len(),
+							text: String::new(),
+						});
 					}
 					Tag::BlockQuote => {
-						write!(&mut html, "<blockquote>").unwrap();
+						if let Some((kind, marker_len)) =
+							callout_marker(&marked.markdown[range.clone()])
+						{
+							blockquotes.push(Some((kind, renderer.html.len())));
+							// The marker starts the first paragraph, which is dropped if nothing follows the marker in it.
+							let marker_end = range.start + marker_len;
+							let paragraph = parser.next_if(|(event, _)| {
+								matches!(event, Event::Start(Tag::Paragraph))
+							});
+							if paragraph.is_some() {
+								while parser
+									.next_if(|(event, text_range)| {
+										matches!(event, Event::Text(_))
+											&& text_range.end <= marker_end
+									})
+									.is_some()
+								{}
+								parser.next_if(|(event, _)| {
+									matches!(event, Event::SoftBreak | Event::HardBreak)
+								});
+								let empty = parser.next_if(|(event, _)| {
+									matches!(event, Event::End(Tag::Paragraph))
+								});
+								if empty.is_none() {
+									renderer.html.push_str("<p>");
+								}
+							}
+						} else {
+							blockquotes.push(None);
+							renderer.html.push_str("<blockquote>");
+						}
 					}
 					Tag::CodeBlock(kind) => {
-						let language = match kind {
-							CodeBlockKind::Indented => None,
-							CodeBlockKind::Fenced(language) => match language.as_ref() {
-								"elixir" => Some(ui::Language::Elixir),
-								"go" => Some(ui::Language::Go),
-								"javscript" => Some(ui::Language::Javascript),
-								"php" => Some(ui::Language::Php),
-								"python" => Some(ui::Language::Python),
-								"ruby" => Some(ui::Language::Ruby),
-								"rust" => Some(ui::Language::Rust),
-								_ => None,
-							},
-						};
-						state = State::Code {
-							code: None,
-							language,
+						let info = match kind {
+							CodeBlockKind::Indented => ui::FenceInfo::default(),
+							CodeBlockKind::Fenced(info) => ui::FenceInfo::parse(&info),
 						};
+						code = Some((String::new(), info));
 					}
 					Tag::List(start) => {
 						if let Some(start) = start {
-							write!(&mut html, "<ol start=\"{start}\">").unwrap();
+							let _ = write!(renderer.html, "<ol start=\"{start}\">");
 						} else {
-							html.push_str("<ul>");
+							renderer.html.push_str("<ul>");
 						}
 					}
 					Tag::Item => {
-						html.push_str("<li>");
+						renderer.html.push_str("<li>");
 					}
-					Tag::FootnoteDefinition(_) => {
-						unimplemented!();
+					Tag::FootnoteDefinition(name) => {
+						defined_footnotes.insert(name.to_string());
+						let number = footnote_number(&mut footnotes, &name);
+						renderer
+							.html
+							.push_str("<div class=\"footnote-definition\" id=\"footnote-");
+						renderer.push_escaped(&name);
+						let _ = write!(
+							renderer.html,
+							"\"><sup class=\"footnote-definition-label\">{number}</sup>"
+						);
 					}
 					Tag::Table(alignments) => {
-						html.push_str("<div class=\"table\"><table>");
-						state = State::Table {
-							part: TablePart::Head,
+						renderer.html.push_str("<div class=\"table\"><table>");
+						table = Some(Table {
+							head: true,
 							alignments,
 							column_index: 0,
-						};
+						});
 					}
 					Tag::TableHead => {
-						html.push_str("<thead>");
+						renderer.html.push_str("<thead>");
 					}
 					Tag::TableRow => {
-						html.push_str("<tr>");
-						match &mut state {
-							State::Table { column_index, .. } => *column_index = 0,
-							_ => unreachable!(),
+						renderer.html.push_str("<tr>");
+						if let Some(table) = &mut table {
+							table.column_index = 0;
 						}
 					}
 					Tag::TableCell => {
-						match &state {
-							State::Table {
-								part: TablePart::Head,
-								..
-							} => html.push_str("<th"),
-							State::Table {
-								part: TablePart::Body,
-								..
-							} => html.push_str("<td"),
-							_ => unreachable!(),
-						};
-						match &mut state {
-							State::Table {
-								alignments,
-								column_index,
-								..
-							} => match alignments.get(*column_index).expect("no alignment") {
-								Alignment::None => {}
-								Alignment::Left => {
-									html.push_str(" style=\"text-align: left;\"");
-								}
-								Alignment::Center => {
-									html.push_str(" style=\"text-align: center;\"");
-								}
-								Alignment::Right => {
-									html.push_str(" style=\"text-align: right;\"");
-								}
-							},
-							_ => unreachable!(),
+						let head = table.as_ref().is_some_and(|table| table.head);
+						renderer.html.push_str(if head { "<th" } else { "<td" });
+						let alignment = table
+							.as_ref()
+							.and_then(|table| table.alignments.get(table.column_index).copied());
+						match alignment {
+							None | Some(Alignment::None) => {}
+							Some(Alignment::Left) => {
+								renderer.html.push_str(" style=\"text-align: left;\"");
+							}
+							Some(Alignment::Center) => {
+								renderer.html.push_str(" style=\"text-align: center;\"");
+							}
+							Some(Alignment::Right) => {
+								renderer.html.push_str(" style=\"text-align: right;\"");
+							}
 						}
-						html.push('>');
+						renderer.html.push('>');
 					}
 					Tag::Emphasis => {
-						html.push_str("<em>");
+						renderer.html.push_str("<em>");
 					}
 					Tag::Strong => {
-						html.push_str("<strong>");
+						renderer.html.push_str("<strong>");
 					}
 					Tag::Strikethrough => {
-						html.push_str("<del>");
+						renderer.html.push_str("<del>");
 					}
 					Tag::Link(_, href, _) => {
-						write!(&mut html, "<a href=\"{href}\">").unwrap();
+						// Links to Rust paths like `std::vec::Vec` link to their documentation.
+						let href = if href.contains("::") || href.contains('@') {
+							doc_link::doc_url(&href).map_or(href, CowStr::from)
+						} else {
+							href
+						};
+						if self.allows_url(&href) {
+							renderer.html.push_str("<a href=\"");
+							renderer.push_escaped_href(&href);
+							renderer.html.push_str("\">");
+						} else {
+							renderer.warn(&range, &format!("removed the link to {href}"));
+							renderer.html.push_str("<a>");
+						}
 					}
-					Tag::Image(_, src, alt) => {
-						let node = ui::Img::new()
-							.alt(alt.into_string())
-							.src(src.into_string())
-							.into_node();
-						write!(&mut html, "{node}").unwrap();
+					Tag::Image(_, src, title) => {
+						let src = if self.allows_url(&src) {
+							Some(src.into_string())
+						} else {
+							renderer.warn(&range, &format!("removed the image at {src}"));
+							None
+						};
+						images.push(Image {
+							start: renderer.html.len(),
+							src,
+							title: title.into_string(),
+							alt: String::new(),
+						});
 					}
 				},
 				Event::End(tag) => match tag {
 					Tag::Paragraph => {
-						html.push_str("</p>");
-					}
-					Tag::Heading(level, id, _) => match &state {
-						State::Heading { heading, .. } => {
-							write!(&mut html, "<h{level}").unwrap();
-							let id = id.map(std::borrow::ToOwned::to_owned).or_else(|| {
-								heading.as_ref().map(|heading| {
-									heading.to_lowercase().to_case(convert_case::Case::Snake)
-								})
-							});
-							if let Some(id) = id {
-								write!(&mut html, " id=\"{id}\"").unwrap();
-							}
-							write!(&mut html, ">").unwrap();
-							if let Some(heading) = heading {
-								write!(&mut html, "{heading}").unwrap();
-							}
-							write!(&mut html, "</h{level}>").unwrap();
-							state = State::Ground;
+						renderer.html.push_str("</p>");
+					}
+					Tag::Heading(level, id, _) => {
+						let Some(heading) = heading.take() else {
+							renderer.warn(&range, "heading ended without starting");
+							continue;
+						};
+						let inner = renderer.html.split_off(heading.start);
+						let id = id.map(std::borrow::ToOwned::to_owned).or_else(|| {
+							(!heading.text.is_empty()).then(|| {
+								heading
+									.text
+									.to_lowercase()
+									.to_case(convert_case::Case::Snake)
+							})
+						});
+						let _ = write!(renderer.html, "<{level}");
+						if let Some(id) = id {
+							renderer.html.push_str(" id=\"");
+							renderer.push_escaped(&id);
+							renderer.html.push('"');
 						}
-						_ => unreachable!(),
-					},
+						renderer.html.push('>');
+						renderer.html.push_str(&inner);
+						let _ = write!(renderer.html, "</{level}>");
+					}
 					Tag::BlockQuote => {
-						write!(&mut html, "</blockquote>").unwrap();
-					}
-					Tag::CodeBlock(_) => match &state {
-						State::Code { language, code } => {
-							let code = code.as_ref().unwrap().to_owned();
-							let code = ui::Code::new().code(Cow::Owned(code)).language(*language);
-							let node = ui::Card::new().child(code).into_node();
-							write!(&mut html, "{node}").unwrap();
-							state = State::Ground;
+						if let Some((kind, start)) = blockquotes.pop().flatten() {
+							let body = renderer.html.split_off(start);
+							let node = ui::Callout::new(kind)
+								.child(div().class("callout-body").inner_html(body))
+								.into_node();
+							renderer.html.push_str(&node.to_string());
+						} else {
+							renderer.html.push_str("</blockquote>");
 						}
-						_ => unreachable!(),
-					},
+					}
+					Tag::CodeBlock(_) => {
+						let Some((code, info)) = code.take() else {
+							renderer.warn(&range, "code block ended without starting");
+							continue;
+						};
+						let playground = if info.language.as_deref() == Some("rust")
+							&& info.has_flag("playground")
+						{
+							// The snippet is wrapped like a doctest, the same way check-snippets compiles it.
+							let source = ui::doctest_source(&ui::all_rust_lines(&code));
+							Some(ui::playground_url(&source, info.edition()))
+						} else {
+							None
+						};
+						let copy_text = copy_text(&code, &info);
+						let code = code_block(code, &info);
+						let copy_button = Dehydrate::new(
+							format!("code-copy-{}", counters.copy_buttons),
+							ui::CopyButton::new(copy_text),
+						);
+						counters.copy_buttons += 1;
+						let header = ui::CodeHeader::new()
+							.copy_button(copy_button.into_node())
+							.playground(playground);
+						let lines = code.code.as_deref().map_or(0, ui::code::count_lines);
+						let code = match self.code_fold_lines {
+							Some(max) if lines > max && !info.has_flag("nofold") => {
+								let fold = ui::CodeFold::new(
+									format!("code-fold-{}", counters.code_folds),
+									code,
+								);
+								counters.code_folds += 1;
+								fold.into_node()
+							}
+							_ => code.into_node(),
+						};
+						if let Some((tabs, _)) = &mut code_group {
+							// The tab shows the title, so the header only holds the buttons.
+							let title = info
+								.title
+								.clone()
+								.or_else(|| info.language.clone())
+								.unwrap_or_else(|| "text".to_owned());
+							let code = fragment().child(header).child(code).into_node();
+							tabs.push(ui::CodeSelectTab { title, code });
+						} else {
+							let header = header
+								.title(info.title.clone())
+								.language(info.language.clone());
+							let node = ui::Card::new().child(header).child(code).into_node();
+							renderer.html.push_str(&node.to_string());
+							renderer.warn_highlight_errors(&range);
+						}
+					}
 					Tag::List(start) => {
 						if start.is_some() {
-							html.push_str("</ol>");
+							renderer.html.push_str("</ol>");
 						} else {
-							html.push_str("</ul>");
+							renderer.html.push_str("</ul>");
 						}
 					}
 					Tag::Item => {
-						html.push_str("</li>");
+						renderer.html.push_str("</li>");
 					}
 					Tag::FootnoteDefinition(_) => {
-						unimplemented!();
+						renderer.html.push_str("</div>");
 					}
 					Tag::Table(_) => {
-						html.push_str("</tbody></table></div>");
-						state = State::Ground;
+						renderer.html.push_str("</tbody></table></div>");
+						table = None;
 					}
 					Tag::TableHead => {
-						html.push_str("</thead><tbody>");
-						match &mut state {
-							State::Table { part, .. } => *part = TablePart::Body,
-							_ => unreachable!(),
-						};
+						renderer.html.push_str("</thead><tbody>");
+						if let Some(table) = &mut table {
+							table.head = false;
+						}
 					}
 					Tag::TableRow => {
-						html.push_str("</tr>");
+						renderer.html.push_str("</tr>");
 					}
 					Tag::TableCell => {
-						match &state {
-							State::Table {
-								part: TablePart::Head,
-								..
-							} => html.push_str("</th>"),
-							State::Table {
-								part: TablePart::Body,
-								..
-							} => html.push_str("</td>"),
-							_ => unreachable!(),
-						};
-						match &mut state {
-							State::Table { column_index, .. } => *column_index += 1,
-							_ => unreachable!(),
-						};
+						let head = table.as_ref().is_some_and(|table| table.head);
+						renderer.html.push_str(if head { "</th>" } else { "</td>" });
+						if let Some(table) = &mut table {
+							table.column_index += 1;
+						}
 					}
 					Tag::Emphasis => {
-						html.push_str("</em>");
+						renderer.html.push_str("</em>");
 					}
 					Tag::Strong => {
-						html.push_str("</strong>");
+						renderer.html.push_str("</strong>");
 					}
 					Tag::Strikethrough => {
-						html.push_str("</del>");
+						renderer.html.push_str("</del>");
 					}
 					Tag::Link(_, _, _) => {
-						html.push_str("</a>");
+						renderer.html.push_str("</a>");
+					}
+					Tag::Image(_, _, _) => {
+						let Some(image) = images.pop() else {
+							renderer.warn(&range, "image ended without starting");
+							continue;
+						};
+						// The alt text is written as markdown, but only its text can go in the attribute.
+						renderer.html.truncate(image.start);
+						// Video files in a post's directory are written as images, like `![A demo](demo.mp4)`.
+						if let Some(src) = image.src.clone().filter(|src| is_video(src)) {
+							let title = (!image.alt.is_empty()).then_some(image.alt);
+							let node = ui::Video::new(src).title(title).into_node();
+							renderer.html.push_str(&node.to_string());
+							continue;
+						}
+						let caption = (!image.title.is_empty()).then_some(image.title);
+						let node = ui::Img::new()
+							.alt(image.alt)
+							.caption(caption)
+							.src(image.src)
+							.into_node();
+						renderer.html.push_str(&node.to_string());
 					}
-					Tag::Image(_, _, _) => {}
 				},
 				Event::Text(text) => {
-					match &mut state {
-						State::Code { code, .. } => *code = Some(text.into_string()),
-						State::Heading { heading, .. } => *heading = Some(text.into_string()),
-						_ => escape_html(&mut html, &text).unwrap(),
-					};
+					if let Some((code, _)) = &mut code {
+						code.push_str(&text);
+					} else {
+						if let Some(heading) = &mut heading {
+							heading.text.push_str(&text);
+						}
+						let text = if self.typography.emoji_shortcodes {
+							replace_emoji_shortcodes(&text)
+						} else {
+							Cow::Borrowed(&*text)
+						};
+						for image in &mut images {
+							image.alt.push_str(&text);
+						}
+						renderer.push_escaped(&text);
+					}
 				}
 				Event::Code(code) => {
-					html.push_str(r#"<span class="inline-code">"#);
-					escape_html(&mut html, &code).unwrap();
-					html.push_str(r"</span>");
+					// A language hint like `{:rust}` directly after inline code selects a grammar to highlight it with.
+					let mut language = None;
+					if let Some((Event::Text(text), hint_range)) = parser.peek_mut() {
+						if let Some((hint, rest)) = text
+							.strip_prefix("{:")
+							.and_then(|text| text.split_once('}'))
+							.filter(|(hint, _)| {
+								!hint.is_empty()
+									&& hint.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+							}) {
+							language = ui::Language::from_name(hint);
+							if language.is_none() {
+								let message = format!("no grammar to highlight inline {hint}");
+								renderer.warn(hint_range, &message);
+							}
+							*text = rest.to_owned().into();
+						}
+					}
+					if let Some(heading) = &mut heading {
+						heading.text.push_str(&code);
+					}
+					for image in &mut images {
+						image.alt.push_str(&code);
+					}
+					let node = ui::InlineCodeBlock::new(code.into_string())
+						.language(language)
+						.into_node();
+					renderer.html.push_str(&node.to_string());
+					renderer.warn_highlight_errors(&range);
 				}
 				Event::Html(raw) => {
-					html.push_str(&raw);
+					if let Some(data) = markers::parse_marker("shortcode", &raw) {
+						let call = data
+							.parse::<usize>()
+							.ok()
+							.and_then(|index| marked.shortcodes.get(index));
+						if let Some(call) = call {
+							let inline = !raw.ends_with('\n');
+							self.render_shortcode(call, inline, counters, &mut renderer);
+						} else {
+							renderer.warn(&range, "invalid shortcode");
+						}
+						continue;
+					}
+					if let Some(data) = markers::parse_marker("math", &raw) {
+						if let Some((mathml, errors)) = math::render_math(&data) {
+							renderer.html.push_str(&mathml);
+							for error in errors {
+								renderer.warn(&range, &format!("invalid math: {error}"));
+							}
+						} else {
+							renderer.warn(&range, "invalid math");
+						}
+						continue;
+					}
+					if let Some(data) = markers::parse_marker("liquid", &raw) {
+						if let Some(node) =
+							liquid::render_liquid_tag(&data, raw.ends_with('\n'), counters)
+						{
+							renderer.html.push_str(&node.to_string());
+						} else {
+							renderer.warn(&range, "invalid liquid tag");
+						}
+						continue;
+					}
+					match raw.trim() {
+						// The end of the excerpt has done its job by the time the whole document is rendered.
+						MORE_MARKER => {}
+						"<!-- code-group -->" => {
+							if code_group.is_some() {
+								renderer
+									.warn(&range, "code group started inside another code group");
+							} else {
+								code_group = Some((Vec::new(), range.start));
+							}
+						}
+						"<!-- /code-group -->" => {
+							if let Some((tabs, _)) = code_group.take() {
+								renderer.push_code_group(tabs, counters.code_groups);
+								counters.code_groups += 1;
+								renderer.warn_highlight_errors(&range);
+							} else {
+								renderer.warn(&range, "code group ended without starting");
+							}
+						}
+						_ => match &self.html_policy {
+							ui::HtmlPolicy::Trusted => renderer.html.push_str(&raw),
+							ui::HtmlPolicy::Sanitized(allowlist) => {
+								let (html, removed) = sanitizer.sanitize(allowlist, &raw);
+								renderer.html.push_str(&html);
+								for removed in removed {
+									renderer.warn(&range, &format!("removed {removed}"));
+								}
+							}
+						},
+					}
+				}
+				Event::FootnoteReference(name) => {
+					let number = footnote_number(&mut footnotes, &name);
+					footnote_references.push((name.to_string(), range));
+					renderer
+						.html
+						.push_str("<sup class=\"footnote-reference\"><a href=\"#footnote-");
+					renderer.push_escaped(&name);
+					let _ = write!(renderer.html, "\">{number}</a></sup>");
 				}
-				Event::FootnoteReference(_reference) => unimplemented!(),
 				Event::SoftBreak => {
-					html.push(' ');
+					renderer.html.push(' ');
 				}
 				Event::HardBreak => {
-					html.push('\n');
+					renderer.html.push('\n');
 				}
 				Event::Rule => {
-					html.push_str("<hr />");
+					renderer.html.push_str("<hr />");
 				}
 				Event::TaskListMarker(checked) => {
-					html.push_str("<input type=\"checkbox\" disabled=\"\"");
+					renderer
+						.html
+						.push_str("<input type=\"checkbox\" disabled=\"\"");
 					if checked {
-						html.push_str("checked=\"\"");
+						renderer.html.push_str("checked=\"\"");
 					}
-					html.push_str(" />");
+					renderer.html.push_str(" />");
 				}
+			}
+		}
+		if let Some((tabs, start)) = code_group {
+			renderer.warn(&(start..start), "code group is never closed");
+			renderer.push_code_group(tabs, counters.code_groups);
+			counters.code_groups += 1;
+			renderer.warn_highlight_errors(&(start..start));
+		}
+		for (name, range) in footnote_references {
+			if !defined_footnotes.contains(&name) {
+				renderer.warn(&range, &format!("footnote {name} is never defined"));
+			}
+		}
+		renderer.warnings.sort_by_key(|warning| warning.offset);
+		RenderedMarkdown {
+			html: renderer.html,
+			warnings: renderer.warnings,
+		}
+	}
+}
+
+impl Markdown {
+	fn options(&self) -> Options {
+		let mut options = Options::all();
+		options.set(
+			Options::ENABLE_SMART_PUNCTUATION,
+			self.typography.smart_punctuation,
+		);
+		options
+	}
+
+	fn allows_url(&self, url: &str) -> bool {
+		match &self.html_policy {
+			ui::HtmlPolicy::Trusted => true,
+			ui::HtmlPolicy::Sanitized(allowlist) => allowlist.allows_url(url),
+		}
+	}
+
+	/// Render a shortcode with its body rendered as markdown. If the shortcode cannot be rendered, its body is rendered alone.
+	fn render_shortcode(
+		&self,
+		call: &shortcode::ShortcodeCall,
+		inline: bool,
+		counters: &mut Counters,
+		renderer: &mut Renderer,
+	) {
+		let body = call.body.clone().map(|range| {
+			let body = Markdown {
+				string: Cow::Owned(self.string[range.clone()].to_owned()),
+				html_policy: self.html_policy.clone(),
+				shortcodes: self.shortcodes.clone(),
+				typography: self.typography,
+				code_fold_lines: self.code_fold_lines,
+			}
+			.render_with(counters);
+			for warning in body.warnings {
+				renderer.push_warning(range.start + warning.offset, &warning.message, warning.kind);
+			}
+			// The body of a shortcode in a paragraph is a single paragraph, which is unwrapped so it can be inline.
+			let html = if inline {
+				body.html
+					.strip_prefix("<p>")
+					.and_then(|html| html.strip_suffix("</p>"))
+					.filter(|html| !html.contains("<p>"))
+					.map_or(body.html.clone(), ToOwned::to_owned)
+			} else {
+				body.html
 			};
+			if inline {
+				span().class("shortcode-body").inner_html(html).into_node()
+			} else {
+				div().class("shortcode-body").inner_html(html).into_node()
+			}
+		});
+		let warn = |renderer: &mut Renderer, message: String| {
+			renderer.warn_original(
+				call.range.start,
+				&format!("shortcode {}: {message}", call.name),
+			);
+		};
+		let Some(render) = self.shortcodes.get(&call.name) else {
+			warn(renderer, "no such shortcode".to_owned());
+			renderer
+				.html
+				.push_str(&body.map(|body| body.to_string()).unwrap_or_default());
+			return;
+		};
+		for (name, value) in &call.arguments {
+			if matches!(name.as_str(), "href" | "src") && !self.allows_url(value) {
+				warn(renderer, format!("removed the url {value}"));
+				renderer
+					.html
+					.push_str(&body.map(|body| body.to_string()).unwrap_or_default());
+				return;
+			}
+		}
+		let mut arguments = call.arguments();
+		let body_html = body.as_ref().map(ToString::to_string);
+		match render(&mut arguments, body) {
+			Ok(node) => {
+				for name in arguments.unused() {
+					warn(renderer, format!("unknown argument {name}"));
+				}
+				renderer.html.push_str(&node.to_string());
+			}
+			Err(error) => {
+				warn(renderer, error.to_string());
+				renderer.html.push_str(&body_html.unwrap_or_default());
+			}
+		}
+	}
+}
+
+/// Replace the shortcodes in `text`, like `:tada:`, with their emoji. Colons around anything else, like the ones in `10:30:45`, are left alone.
+fn replace_emoji_shortcodes(text: &str) -> Cow<'_, str> {
+	if !text.contains(':') {
+		return Cow::Borrowed(text);
+	}
+	let mut replaced = String::new();
+	let mut rest = text;
+	while let Some(start) = rest.find(':') {
+		let after = &rest[start + 1..];
+		let emoji = after
+			.find(':')
+			.map(|len| &after[..len])
+			.filter(|name| {
+				name.chars()
+					.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'))
+			})
+			.and_then(|name| Some((name, emojis::get_by_shortcode(name)?)));
+		if let Some((name, emoji)) = emoji {
+			replaced.push_str(&rest[..start]);
+			replaced.push_str(emoji.as_str());
+			rest = &after[name.len() + 1..];
+		} else {
+			replaced.push_str(&rest[..=start]);
+			rest = after;
+		}
+	}
+	replaced.push_str(rest);
+	Cow::Owned(replaced)
+}
+
+fn is_video(src: &str) -> bool {
+	let path = src.split(['?', '#']).next().unwrap_or(src);
+	path.rsplit_once('.').is_some_and(|(_, extension)| {
+		["m4v", "mp4", "ogv", "webm"]
+			.iter()
+			.any(|video| extension.eq_ignore_ascii_case(video))
+	})
+}
+
+/// The kind of callout a blockquote is, and the length of its marker, if its first line is a GitHub-style marker like `> [!NOTE]`.
+fn callout_marker(blockquote: &str) -> Option<(ui::CalloutKind, usize)> {
+	let first_line = blockquote.lines().next()?;
+	let marker = first_line
+		.trim_start()
+		.strip_prefix('>')?
+		.trim_start()
+		.strip_prefix("[!")?;
+	let (name, rest) = marker.split_once(']')?;
+	if !rest.trim().is_empty() {
+		return None;
+	}
+	let kind = ui::CalloutKind::parse(name)?;
+	Some((kind, first_line.len() - rest.len()))
+}
+
+/// Counts of the elements numbered across a document, including the bodies of its shortcodes.
+#[derive(Default)]
+pub(crate) struct Counters {
+	/// Copy buttons and video embeds are numbered from zero so the client can find them all.
+	pub copy_buttons: usize,
+	pub code_groups: usize,
+	pub code_folds: usize,
+	pub video_embeds: usize,
+}
+
+impl Component for Markdown {
+	fn into_node(self) -> Node {
+		self.render().into_node()
+	}
+}
+
+impl Component for RenderedMarkdown {
+	fn into_node(self) -> Node {
+		for warning in &self.warnings {
+			tracing::warn!(%warning, "markdown");
+		}
+		div().class("markdown").inner_html(self.html).into_node()
+	}
+}
+
+struct Renderer<'a> {
+	source: &'a str,
+	marked: &'a markers::MarkedMarkdown,
+	html: String,
+	warnings: Vec<MarkdownWarning>,
+}
+
+impl Renderer<'_> {
+	/// Warn about the element at `range` of the marked markdown being parsed.
+	fn warn(&mut self, range: &std::ops::Range<usize>, message: &str) {
+		let offset = self.marked.original_offset(range.start);
+		self.warn_original(offset, message);
+	}
+
+	/// Warn about the element at `offset` into the markdown as written.
+	fn warn_original(&mut self, offset: usize, message: &str) {
+		self.push_warning(offset, message, MarkdownWarningKind::Markup);
+	}
+
+	fn push_warning(&mut self, offset: usize, message: &str, kind: MarkdownWarningKind) {
+		let offset = offset.min(self.source.len());
+		let line = self
+			.source
+			.get(..offset)
+			.unwrap_or(self.source)
+			.matches('\n')
+			.count() + 1;
+		self.warnings.push(MarkdownWarning {
+			line,
+			offset,
+			message: message.to_owned(),
+			kind,
+		});
+	}
+
+	/// Report the code that could not be highlighted while rendering the element at `range`.
+	fn warn_highlight_errors(&mut self, range: &std::ops::Range<usize>) {
+		for error in ui::take_highlight_errors() {
+			let offset = self.marked.original_offset(range.start);
+			self.push_warning(offset, &error.to_string(), MarkdownWarningKind::Highlight);
 		}
-		markdown.inner_html(html).into_node()
 	}
+
+	fn push_escaped(&mut self, text: &str) {
+		sanitize::push_escaped(&mut self.html, text);
+	}
+
+	fn push_escaped_href(&mut self, href: &str) {
+		// `escape_href` escapes `&` and quotes itself, so escaping its output again would turn `&` in a query into `&amp;amp;`.
+		let _ = escape_href(&mut self.html, href);
+	}
+
+	fn push_code_group(&mut self, tabs: Vec<ui::CodeSelectTab>, number: usize) {
+		let node = ui::CodeSelect::new()
+			.id(format!("code-group-{number}"))
+			.tabs(tabs)
+			.into_node();
+		self.html.push_str(&node.to_string());
+	}
+}
+
+struct Table {
+	head: bool,
+	alignments: Vec<Alignment>,
+	column_index: usize,
+}
+
+/// A heading being rendered, from where its html starts and with its text so far to derive its id from.
+struct Heading {
+	start: usize,
+	text: String,
+}
+
+/// An image being rendered, from where the html of its alt text starts.
+struct Image {
+	start: usize,
+	src: Option<String>,
+	title: String,
+	alt: String,
+}
+
+fn footnote_number(footnotes: &mut HashMap<String, usize>, name: &str) -> usize {
+	let next = footnotes.len() + 1;
+	*footnotes.entry(name.to_owned()).or_insert(next)
+}
+
+/// The text copied by a code block's copy button: Rust without its hidden lines, and shell sessions without prompts or output.
+fn copy_text(code: &str, info: &ui::FenceInfo) -> String {
+	if info.language.as_deref() == Some("rust") {
+		ui::visible_rust_lines(code)
+	} else if info.is_shell_session() {
+		ui::session_commands(code).unwrap_or_else(|| code.to_owned())
+	} else {
+		code.to_owned()
+	}
+}
+
+/// Whether `event`, outside any code block, can be in a code group: the code blocks themselves and the comment that ends the group.
+fn belongs_in_code_group(event: &Event) -> bool {
+	match event {
+		Event::Start(Tag::CodeBlock(_)) => true,
+		Event::Html(raw) => raw.trim().is_empty() || raw.trim() == "<!-- /code-group -->",
+		_ => false,
+	}
+}
+
+fn code_block(code: String, info: &ui::FenceInfo) -> ui::Code {
+	let code = if info.language.as_deref() == Some("rust") {
+		ui::visible_rust_lines(&code)
+	} else {
+		code
+	};
+	ui::Code::new()
+		.ansi(info.is_ansi())
+		.code(Cow::Owned(code))
+		.diff(info.is_diff())
+		.highlight_lines(info.highlight_lines.clone())
+		.language(info.highlight_language())
+		.line_numbers(info.line_numbers)
+		.shell_session(info.is_shell_session())
 }
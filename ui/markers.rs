@@ -0,0 +1,138 @@
+//! Syntax that markdown does not know about, like liquid tags, math and shortcodes, is replaced with markers like `<x-shortcode data="0" />` before parsing. The renderer turns each marker into a component when it comes across it.
+//!
+//! A marker alone on its line is parsed as an html block and one in a paragraph as inline html, so the renderer can tell which the syntax it replaced was.
+use crate::{liquid, math, shortcode};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use std::ops::Range;
+
+/// Markdown with its liquid tags, math and shortcodes replaced by markers.
+pub(crate) struct MarkedMarkdown {
+	pub markdown: String,
+	/// Where each marker ends in the marked markdown and where what it replaced ended in the original, to map offsets back.
+	offsets: Vec<(usize, usize)>,
+	pub shortcodes: Vec<shortcode::ShortcodeCall>,
+	/// Problems found while marking, by their offset into the original markdown.
+	pub warnings: Vec<(usize, String)>,
+}
+
+impl MarkedMarkdown {
+	/// Map an offset in the marked markdown back to the original.
+	pub fn original_offset(&self, offset: usize) -> usize {
+		self.offsets
+			.iter()
+			.rev()
+			.find(|(marked_end, _)| *marked_end <= offset)
+			.map_or(offset, |(marked_end, original_end)| {
+				original_end + (offset - marked_end)
+			})
+	}
+}
+
+pub(crate) fn mark(markdown: &str, options: Options) -> MarkedMarkdown {
+	let code_ranges = code_ranges(markdown, options);
+	let mut warnings = Vec::new();
+	let (shortcodes, shortcode_warnings) = shortcode::find_shortcodes(markdown, &code_ranges);
+	warnings.extend(shortcode_warnings);
+	// Liquid tags in the body of a shortcode are found when the body is rendered.
+	let skip = code_ranges
+		.iter()
+		.cloned()
+		.chain(shortcodes.iter().map(|shortcode| shortcode.range.clone()))
+		.collect::<Vec<_>>();
+	let mut replacements = Vec::new();
+	for tag in liquid::find_liquid_tags(markdown, &skip) {
+		if let Some(marker) = liquid::liquid_marker(&tag) {
+			replacements.push((tag.range, marker));
+		} else {
+			warnings.push((
+				tag.range.start,
+				format!("unsupported liquid tag {}", tag.name),
+			));
+		}
+	}
+	// Dollar signs in liquid tags and shortcodes are left for them.
+	let skip = skip
+		.into_iter()
+		.chain(replacements.iter().map(|(range, _)| range.clone()))
+		.collect::<Vec<_>>();
+	for math in math::find_math(markdown, &skip) {
+		replacements.push((math.range.clone(), math::math_marker(&math)));
+	}
+	for (index, shortcode) in shortcodes.iter().enumerate() {
+		replacements.push((
+			shortcode.range.clone(),
+			marker("shortcode", &index.to_string()),
+		));
+	}
+	replacements.sort_by_key(|(range, _)| range.start);
+	let mut marked = String::new();
+	let mut offsets = Vec::new();
+	let mut position = 0;
+	for (range, marker) in replacements {
+		// Syntax found inside other syntax, like a shortcode in a liquid tag, is left to what contains it.
+		if range.start < position {
+			continue;
+		}
+		marked.push_str(&markdown[position..range.start]);
+		marked.push_str(&marker);
+		position = range.end;
+		offsets.push((marked.len(), position));
+	}
+	marked.push_str(&markdown[position..]);
+	MarkedMarkdown {
+		markdown: marked,
+		offsets,
+		shortcodes,
+		warnings,
+	}
+}
+
+/// The ranges of the code blocks and inline code in `markdown`, where markers are never placed.
+fn code_ranges(markdown: &str, options: Options) -> Vec<Range<usize>> {
+	let mut code_ranges = Vec::new();
+	let mut code_block_start = None;
+	for (event, range) in Parser::new_ext(markdown, options).into_offset_iter() {
+		match event {
+			Event::Start(Tag::CodeBlock(_)) => code_block_start = Some(range.start),
+			Event::End(Tag::CodeBlock(_)) => {
+				code_ranges.push(code_block_start.take().unwrap_or(range.start)..range.end);
+			}
+			Event::Code(_) => code_ranges.push(range),
+			_ => {}
+		}
+	}
+	code_ranges
+}
+
+/// The marker for syntax of `kind`, carrying `data` for the renderer.
+pub(crate) fn marker(kind: &str, data: &str) -> String {
+	format!("<x-{kind} data=\"{}\" />", encode(data))
+}
+
+/// The data of a marker of `kind`, or `None` if `raw` is not one.
+pub(crate) fn parse_marker(kind: &str, raw: &str) -> Option<String> {
+	let data = raw
+		.trim()
+		.strip_prefix("<x-")?
+		.strip_prefix(kind)?
+		.strip_prefix(" data=\"")?
+		.strip_suffix("\" />")?;
+	Some(decode(data))
+}
+
+/// Percent encode what would end the attribute, the tag or the line.
+fn encode(text: &str) -> String {
+	text.replace('%', "%25")
+		.replace('\n', "%0A")
+		.replace('"', "%22")
+		.replace('<', "%3C")
+		.replace('>', "%3E")
+}
+
+fn decode(text: &str) -> String {
+	text.replace("%0A", "\n")
+		.replace("%22", "\"")
+		.replace("%3C", "<")
+		.replace("%3E", ">")
+		.replace("%25", "%")
+}
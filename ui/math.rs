@@ -0,0 +1,105 @@
+//! TeX math like `$e^{i\pi} + 1 = 0$` and `$$\sum_{n=1}^\infty 2^{-n} = 1$$`, rendered to `<math>` elements so browsers display it without any javascript.
+use crate::markers;
+use pulldown_latex::{config::DisplayMode, mathml::push_mathml, Parser, RenderConfig, Storage};
+use std::ops::Range;
+
+/// Math found in markdown, outside code.
+pub(crate) struct Math {
+	pub range: Range<usize>,
+	/// Whether the math was written between `$$`, to be displayed on its own line.
+	pub display: bool,
+	pub tex: String,
+}
+
+/// Find the math in `markdown` outside the `skip` ranges. Inline math opens with a `$` before a non-space and closes on the same line with a `$` after a non-space and not before a digit, so prices like $5 and $10 are left as text. A dollar sign escaped as `\$` never delimits math.
+pub(crate) fn find_math(markdown: &str, skip: &[Range<usize>]) -> Vec<Math> {
+	let skipped = |offset: usize| skip.iter().any(|range| range.contains(&offset));
+	let mut math = Vec::new();
+	let mut search_start = 0;
+	while let Some(index) = markdown[search_start..].find('$') {
+		let start = search_start + index;
+		search_start = start + 1;
+		if skipped(start) || is_escaped(markdown, start) {
+			continue;
+		}
+		let (display, tex_len, delimiter_len) = if markdown[start..].starts_with("$$") {
+			let Some(tex_len) = markdown[start + 2..].find("$$") else {
+				continue;
+			};
+			(true, tex_len, 2)
+		} else {
+			let rest = &markdown[start + 1..];
+			let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+			if line.starts_with(char::is_whitespace) {
+				continue;
+			}
+			let close = line.char_indices().find(|(index, c)| {
+				*c == '$'
+					&& *index > 0 && !line[..*index].ends_with(char::is_whitespace)
+					&& !is_escaped(line, *index)
+					&& !line[index + 1..].starts_with(|c: char| c.is_ascii_digit())
+			});
+			let Some((tex_len, _)) = close else {
+				continue;
+			};
+			(false, tex_len, 1)
+		};
+		let end = start + delimiter_len + tex_len + delimiter_len;
+		let tex = markdown[start + delimiter_len..end - delimiter_len].trim();
+		// Math never takes part of a liquid tag or shortcode with it.
+		let overlaps_skipped = skip
+			.iter()
+			.any(|range| range.start < end && start < range.end);
+		if tex.is_empty() || overlaps_skipped {
+			continue;
+		}
+		math.push(Math {
+			range: start..end,
+			display,
+			tex: tex.to_owned(),
+		});
+		search_start = end;
+	}
+	math
+}
+
+fn is_escaped(text: &str, index: usize) -> bool {
+	text[..index]
+		.chars()
+		.rev()
+		.take_while(|c| *c == '\\')
+		.count()
+		% 2 == 1
+}
+
+pub(crate) fn math_marker(math: &Math) -> String {
+	let mode = if math.display { "display" } else { "inline" };
+	markers::marker("math", &format!("{mode} {}", math.tex))
+}
+
+/// Render the math in a marker's data to a `<math>` element, with the TeX source as an annotation. Anything that could not be parsed is rendered as an error in place and returned.
+pub(crate) fn render_math(data: &str) -> Option<(String, Vec<String>)> {
+	let (mode, tex) = data.split_once(' ')?;
+	let display_mode = match mode {
+		"display" => DisplayMode::Block,
+		"inline" => DisplayMode::Inline,
+		_ => return None,
+	};
+	let storage = Storage::new();
+	let mut errors = Vec::new();
+	let parser = Parser::new(tex, &storage).inspect(|event| {
+		if let Err(error) = event {
+			let error = error.to_string();
+			let message = error.lines().next().unwrap_or_default();
+			errors.push(message.trim_start_matches("parsing error: ").to_owned());
+		}
+	});
+	let config = RenderConfig {
+		display_mode,
+		annotation: Some(tex),
+		..RenderConfig::default()
+	};
+	let mut html = String::new();
+	push_mathml(&mut html, parser, config).ok()?;
+	Some((html, errors))
+}
@@ -0,0 +1,350 @@
+use std::borrow::Cow;
+
+/// How raw html written in markdown is rendered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HtmlPolicy {
+	/// Render raw html as written, for first-party posts.
+	Trusted,
+	/// Keep only the allowed tags and attributes, dropping everything else, for markdown from imported or guest sources.
+	Sanitized(HtmlAllowlist),
+}
+
+impl Default for HtmlPolicy {
+	fn default() -> Self {
+		HtmlPolicy::Sanitized(HtmlAllowlist::default())
+	}
+}
+
+/// The raw html allowed by [`HtmlPolicy::Sanitized`]. Scripts, styles and event handler attributes are never allowed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HtmlAllowlist {
+	pub tags: Vec<Cow<'static, str>>,
+	/// The attributes allowed on any allowed tag.
+	pub attributes: Vec<Cow<'static, str>>,
+	/// The schemes allowed in urls. Relative urls are always allowed.
+	pub url_schemes: Vec<Cow<'static, str>>,
+}
+
+impl Default for HtmlAllowlist {
+	fn default() -> Self {
+		HtmlAllowlist {
+			tags: [
+				"a",
+				"abbr",
+				"b",
+				"blockquote",
+				"br",
+				"caption",
+				"cite",
+				"code",
+				"col",
+				"colgroup",
+				"dd",
+				"del",
+				"details",
+				"dfn",
+				"div",
+				"dl",
+				"dt",
+				"em",
+				"figcaption",
+				"figure",
+				"h1",
+				"h2",
+				"h3",
+				"h4",
+				"h5",
+				"h6",
+				"hr",
+				"i",
+				"img",
+				"ins",
+				"kbd",
+				"li",
+				"mark",
+				"ol",
+				"p",
+				"pre",
+				"q",
+				"s",
+				"samp",
+				"small",
+				"span",
+				"strong",
+				"sub",
+				"summary",
+				"sup",
+				"table",
+				"tbody",
+				"td",
+				"tfoot",
+				"th",
+				"thead",
+				"time",
+				"tr",
+				"u",
+				"ul",
+				"var",
+			]
+			.into_iter()
+			.map(Cow::Borrowed)
+			.collect(),
+			attributes: [
+				"alt", "cite", "colspan", "datetime", "height", "href", "lang", "open", "rowspan",
+				"src", "start", "title", "width",
+			]
+			.into_iter()
+			.map(Cow::Borrowed)
+			.collect(),
+			url_schemes: ["http", "https", "mailto"]
+				.into_iter()
+				.map(Cow::Borrowed)
+				.collect(),
+		}
+	}
+}
+
+/// Tags whose content is dropped along with them, because it is not meant to be read as text.
+const DROP_CONTENT_TAGS: &[&str] = &[
+	"iframe", "noembed", "noscript", "object", "script", "style", "template", "textarea", "title",
+	"xmp",
+];
+
+const URL_ATTRIBUTES: &[&str] = &["action", "cite", "formaction", "href", "poster", "src"];
+
+impl HtmlAllowlist {
+	/// Whether a url may be linked to: relative, or with an allowed scheme.
+	#[must_use]
+	pub fn allows_url(&self, url: &str) -> bool {
+		// Browsers ignore control characters and whitespace in schemes, so `java\tscript:` is still `javascript:`.
+		let url = url
+			.chars()
+			.filter(|c| !c.is_ascii_control() && !c.is_whitespace())
+			.collect::<String>();
+		let scheme_end = url.find([':', '/', '?', '#']);
+		match scheme_end {
+			Some(index) if url[index..].starts_with(':') => self
+				.url_schemes
+				.iter()
+				.any(|scheme| scheme.eq_ignore_ascii_case(&url[..index])),
+			_ => true,
+		}
+	}
+}
+
+/// Sanitizes raw html a fragment at a time, because markdown splits an html block into one fragment per line and interleaves inline html with text.
+#[derive(Default)]
+pub(crate) struct Sanitizer {
+	/// The tag whose content is being dropped until it closes.
+	dropping: Option<String>,
+}
+
+impl Sanitizer {
+	/// Sanitize a fragment of html, returning the html to render and a description of everything that was removed.
+	pub(crate) fn sanitize(
+		&mut self,
+		allowlist: &HtmlAllowlist,
+		raw: &str,
+	) -> (String, Vec<String>) {
+		let mut html = String::new();
+		let mut removed = Vec::new();
+		let mut rest = raw;
+		while !rest.is_empty() {
+			let Some(index) = rest.find('<') else {
+				if self.dropping.is_none() {
+					html.push_str(rest);
+				}
+				break;
+			};
+			if self.dropping.is_none() {
+				html.push_str(&rest[..index]);
+			}
+			rest = &rest[index..];
+			if let Some(comment) = rest.strip_prefix("<!--") {
+				rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+				continue;
+			}
+			let Some((tag, len)) = Tag::parse(rest) else {
+				// A `<` that does not start a tag is text.
+				if self.dropping.is_none() {
+					html.push_str("&lt;");
+				}
+				rest = &rest[1..];
+				continue;
+			};
+			rest = &rest[len..];
+			if let Some(dropping) = &self.dropping {
+				if tag.closing && &tag.name == dropping {
+					self.dropping = None;
+				}
+				continue;
+			}
+			if DROP_CONTENT_TAGS.contains(&tag.name.as_str()) {
+				if !tag.closing && !tag.self_closing {
+					self.dropping = Some(tag.name.clone());
+				}
+				if !tag.closing {
+					removed.push(format!("<{}> and its content", tag.name));
+				}
+				continue;
+			}
+			if !allowlist.tags.iter().any(|allowed| allowed == &tag.name) {
+				if !tag.closing {
+					removed.push(format!("<{}>", tag.name));
+				}
+				continue;
+			}
+			if tag.closing {
+				html.push_str("</");
+				html.push_str(&tag.name);
+				html.push('>');
+				continue;
+			}
+			html.push('<');
+			html.push_str(&tag.name);
+			for (name, value) in tag.attributes {
+				let allowed = !name.starts_with("on")
+					&& allowlist.attributes.iter().any(|allowed| allowed == &name)
+					&& (!URL_ATTRIBUTES.contains(&name.as_str())
+						|| value
+							.as_deref()
+							.map_or(true, |url| allowlist.allows_url(url)));
+				if !allowed {
+					removed.push(format!("the {name} attribute of <{}>", tag.name));
+					continue;
+				}
+				html.push(' ');
+				html.push_str(&name);
+				if let Some(value) = value {
+					html.push_str("=\"");
+					push_escaped(&mut html, &value);
+					html.push('"');
+				}
+			}
+			if tag.self_closing {
+				html.push_str(" /");
+			}
+			html.push('>');
+		}
+		(html, removed)
+	}
+}
+
+struct Tag {
+	name: String,
+	closing: bool,
+	self_closing: bool,
+	attributes: Vec<(String, Option<String>)>,
+}
+
+impl Tag {
+	/// Parse the tag at the start of `html`, returning it and its length, or `None` if `html` does not start with a complete tag.
+	fn parse(html: &str) -> Option<(Tag, usize)> {
+		let mut chars = html.char_indices().peekable();
+		chars.next().filter(|(_, c)| *c == '<')?;
+		let closing = chars.next_if(|(_, c)| *c == '/').is_some();
+		let mut name = String::new();
+		while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '-') {
+			name.push(c.to_ascii_lowercase());
+		}
+		if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+			return None;
+		}
+		let mut tag = Tag {
+			name,
+			closing,
+			self_closing: false,
+			attributes: Vec::new(),
+		};
+		loop {
+			while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+			let (index, c) = chars.next()?;
+			match c {
+				'>' => return Some((tag, index + 1)),
+				'/' => tag.self_closing = true,
+				_ => {
+					let mut name = c.to_ascii_lowercase().to_string();
+					while let Some((_, c)) =
+						chars.next_if(|(_, c)| !c.is_whitespace() && !matches!(c, '=' | '>' | '/'))
+					{
+						name.push(c.to_ascii_lowercase());
+					}
+					while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+					let value = if chars.next_if(|(_, c)| *c == '=').is_some() {
+						while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+						let mut value = String::new();
+						match chars.next_if(|(_, c)| matches!(c, '"' | '\'')) {
+							Some((_, quote)) => loop {
+								let (_, c) = chars.next()?;
+								if c == quote {
+									break;
+								}
+								value.push(c);
+							},
+							None => {
+								while let Some((_, c)) =
+									chars.next_if(|(_, c)| !c.is_whitespace() && *c != '>')
+								{
+									value.push(c);
+								}
+							}
+						}
+						Some(unescape(&value))
+					} else {
+						None
+					};
+					tag.self_closing = false;
+					tag.attributes.push((name, value));
+				}
+			}
+		}
+	}
+}
+
+/// Append `text` to `html`, escaping the characters html gives meaning to.
+pub(crate) fn push_escaped(html: &mut String, text: &str) {
+	// Writing to a `String` cannot fail.
+	let _ = pulldown_cmark::escape::escape_html(html, text);
+}
+
+/// Decode the character references an attribute value may hide a url scheme behind, like `&#106;avascript:`.
+fn unescape(value: &str) -> String {
+	let mut unescaped = String::new();
+	let mut rest = value;
+	while let Some(index) = rest.find('&') {
+		unescaped.push_str(&rest[..index]);
+		rest = &rest[index..];
+		let reference = rest[1..]
+			.find(';')
+			.map(|end| (&rest[1..=end], end + 2))
+			.and_then(|(reference, len)| {
+				let c = match reference {
+					"amp" => Some('&'),
+					"apos" => Some('\''),
+					"colon" => Some(':'),
+					"gt" => Some('>'),
+					"lt" => Some('<'),
+					"quot" => Some('"'),
+					"tab" => Some('\t'),
+					_ => {
+						let number = reference.strip_prefix('#')?;
+						let code = match number.strip_prefix(['x', 'X']) {
+							Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+							None => number.parse().ok()?,
+						};
+						char::from_u32(code)
+					}
+				};
+				c.map(|c| (c, len))
+			});
+		if let Some((c, len)) = reference {
+			unescaped.push(c);
+			rest = &rest[len..];
+		} else {
+			unescaped.push('&');
+			rest = &rest[1..];
+		}
+	}
+	unescaped.push_str(rest);
+	unescaped
+}
@@ -0,0 +1,293 @@
+//! Shortcodes like `{{< card title="Notes" >}}Some *markdown*{{< /card >}}`, which render components from markdown without dropping to raw html.
+use crate as ui;
+use pinwheel::prelude::*;
+use std::{collections::HashMap, fmt::Display, ops::Range, str::FromStr, sync::Arc};
+
+/// Renders a shortcode from its arguments and its body, if it has one.
+pub type ShortcodeRender =
+	dyn Fn(&mut ShortcodeArguments, Option<Node>) -> Result<Node, ShortcodeError> + Send + Sync;
+
+/// The shortcodes markdown can use, by name.
+#[derive(Clone)]
+pub struct Shortcodes {
+	renderers: Arc<HashMap<String, Arc<ShortcodeRender>>>,
+}
+
+impl Shortcodes {
+	/// No shortcodes, not even the built in ones.
+	#[must_use]
+	pub fn empty() -> Shortcodes {
+		Shortcodes {
+			renderers: Arc::new(HashMap::new()),
+		}
+	}
+
+	#[must_use]
+	pub fn register(
+		mut self,
+		name: impl Into<String>,
+		render: impl Fn(&mut ShortcodeArguments, Option<Node>) -> Result<Node, ShortcodeError>
+			+ Send
+			+ Sync
+			+ 'static,
+	) -> Shortcodes {
+		Arc::make_mut(&mut self.renderers).insert(name.into(), Arc::new(render));
+		self
+	}
+
+	pub(crate) fn get(&self, name: &str) -> Option<&ShortcodeRender> {
+		self.renderers.get(name).map(AsRef::as_ref)
+	}
+}
+
+/// The built in shortcodes: `card`, `details`, `img` and `link`.
+impl Default for Shortcodes {
+	fn default() -> Self {
+		static BUILT_IN: once_cell::sync::Lazy<Shortcodes> = once_cell::sync::Lazy::new(|| {
+			Shortcodes::empty()
+				.register("card", |arguments, body| {
+					let title = arguments
+						.get::<String>("title")?
+						.map(|title| div().class("card-title").child(title));
+					Ok(ui::Card::new().child(title).child(body).into_node())
+				})
+				.register("details", |arguments, body| {
+					Ok(ui::Details::new()
+						.open(arguments.get::<bool>("open")?.unwrap_or(false))
+						.summary(arguments.get::<String>("summary")?)
+						.child(body)
+						.into_node())
+				})
+				.register("img", |arguments, _| {
+					Ok(ui::Img::new()
+						.alt(arguments.get::<String>("alt")?)
+						.caption(arguments.get::<String>("caption")?)
+						.src(arguments.required::<String>("src")?)
+						.into_node())
+				})
+				.register("link", |arguments, body| {
+					let href = arguments.required::<String>("href")?;
+					let text = arguments.get::<String>("text")?;
+					let children = body
+						.or_else(|| text.map(Node::from))
+						.unwrap_or_else(|| Node::from(href.clone()));
+					Ok(ui::Link::new()
+						.href(href)
+						.title(arguments.get::<String>("title")?)
+						.child(children)
+						.into_node())
+				})
+		});
+		BUILT_IN.clone()
+	}
+}
+
+impl PartialEq for Shortcodes {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.renderers, &other.renderers)
+	}
+}
+
+impl Eq for Shortcodes {}
+
+/// The `key="value"` arguments a shortcode was written with, parsed to the types the shortcode asks for.
+pub struct ShortcodeArguments {
+	arguments: Vec<(String, String)>,
+}
+
+impl ShortcodeArguments {
+	pub fn get<T>(&mut self, key: &str) -> Result<Option<T>, ShortcodeError>
+	where
+		T: FromStr,
+		T::Err: Display,
+	{
+		let Some(index) = self.arguments.iter().position(|(name, _)| name == key) else {
+			return Ok(None);
+		};
+		let (_, value) = self.arguments.remove(index);
+		value
+			.parse()
+			.map(Some)
+			.map_err(|error| ShortcodeError::new(format!("invalid {key} {value:?}: {error}")))
+	}
+
+	pub fn required<T>(&mut self, key: &str) -> Result<T, ShortcodeError>
+	where
+		T: FromStr,
+		T::Err: Display,
+	{
+		self.get(key)?
+			.ok_or_else(|| ShortcodeError::new(format!("missing {key}")))
+	}
+
+	/// The names of the arguments the shortcode did not ask for.
+	pub(crate) fn unused(&self) -> impl Iterator<Item = &str> {
+		self.arguments.iter().map(|(name, _)| name.as_str())
+	}
+}
+
+#[derive(Debug)]
+pub struct ShortcodeError(String);
+
+impl ShortcodeError {
+	pub fn new(message: impl Into<String>) -> ShortcodeError {
+		ShortcodeError(message.into())
+	}
+}
+
+impl Display for ShortcodeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl std::error::Error for ShortcodeError {}
+
+/// A shortcode found in markdown, outside code.
+pub(crate) struct ShortcodeCall {
+	/// The range of the whole shortcode in the markdown, from its opening tag to its closing tag.
+	pub range: Range<usize>,
+	pub name: String,
+	pub arguments: Vec<(String, String)>,
+	/// The range of the markdown between the opening and closing tags.
+	pub body: Option<Range<usize>>,
+}
+
+impl ShortcodeCall {
+	pub fn arguments(&self) -> ShortcodeArguments {
+		ShortcodeArguments {
+			arguments: self.arguments.clone(),
+		}
+	}
+}
+
+struct ShortcodeTag {
+	range: Range<usize>,
+	name: String,
+	closing: bool,
+	self_closing: bool,
+	arguments: Vec<(String, String)>,
+}
+
+/// Find the outermost shortcodes in `markdown` outside `code_ranges`. The shortcodes nested in their bodies are found when the bodies are rendered. Problems are returned by their offset.
+pub(crate) fn find_shortcodes(
+	markdown: &str,
+	code_ranges: &[Range<usize>],
+) -> (Vec<ShortcodeCall>, Vec<(usize, String)>) {
+	let mut shortcodes = Vec::new();
+	let mut warnings = Vec::new();
+	let mut tags = Vec::new();
+	let mut search_start = 0;
+	while let Some(index) = markdown[search_start..].find("{{<") {
+		let start = search_start + index;
+		search_start = start + 3;
+		if code_ranges.iter().any(|range| range.contains(&start)) {
+			continue;
+		}
+		match parse_tag(markdown, start) {
+			Some(tag) => {
+				search_start = tag.range.end;
+				tags.push(tag);
+			}
+			None => warnings.push((start, "invalid shortcode".to_owned())),
+		}
+	}
+	let mut index = 0;
+	while let Some(tag) = tags.get(index) {
+		index += 1;
+		if tag.closing {
+			let message = format!("shortcode {} closed without being opened", tag.name);
+			warnings.push((tag.range.start, message));
+			continue;
+		}
+		let mut body = None;
+		let mut end = tag.range.end;
+		if !tag.self_closing {
+			// Find the matching closing tag, counting the same shortcode nested in the body.
+			let mut depth = 0;
+			for (inner_index, inner) in tags.iter().enumerate().skip(index) {
+				if inner.name != tag.name || inner.self_closing {
+					continue;
+				}
+				if !inner.closing {
+					depth += 1;
+				} else if depth > 0 {
+					depth -= 1;
+				} else {
+					body = Some(tag.range.end..inner.range.start);
+					end = inner.range.end;
+					index = inner_index + 1;
+					break;
+				}
+			}
+		}
+		shortcodes.push(ShortcodeCall {
+			range: tag.range.start..end,
+			name: tag.name.clone(),
+			arguments: tag.arguments.clone(),
+			body,
+		});
+	}
+	(shortcodes, warnings)
+}
+
+/// Parse the shortcode tag starting at `start`, like `{{< card title="Notes" >}}`, `{{< /card >}}` or `{{< img src=cat.png />}}`.
+fn parse_tag(markdown: &str, start: usize) -> Option<ShortcodeTag> {
+	let len = markdown[start..].find(">}}")?;
+	let end = start + len + 3;
+	let inner = markdown[start + 3..end - 3].trim();
+	if inner.contains("{{<") {
+		return None;
+	}
+	let (inner, self_closing) = match inner.strip_suffix('/') {
+		Some(inner) => (inner.trim_end(), true),
+		None => (inner, false),
+	};
+	let (inner, closing) = match inner.strip_prefix('/') {
+		Some(inner) => (inner.trim_start(), true),
+		None => (inner, false),
+	};
+	let name_len = inner
+		.find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_')))
+		.unwrap_or(inner.len());
+	let (name, mut rest) = inner.split_at(name_len);
+	if name.is_empty() {
+		return None;
+	}
+	let mut arguments = Vec::new();
+	loop {
+		rest = rest.trim_start();
+		if rest.is_empty() {
+			break;
+		}
+		let (key, after_key) = rest.split_once('=')?;
+		let key = key.trim();
+		if key.is_empty() || key.contains(char::is_whitespace) {
+			return None;
+		}
+		let after_key = after_key.trim_start();
+		let (value, after_value) = match after_key.chars().next()? {
+			quote @ ('"' | '\'') => {
+				let value_len = after_key[1..].find(quote)?;
+				(&after_key[1..=value_len], &after_key[value_len + 2..])
+			}
+			_ => after_key.split_at(
+				after_key
+					.find(char::is_whitespace)
+					.unwrap_or(after_key.len()),
+			),
+		};
+		arguments.push((key.to_owned(), value.to_owned()));
+		rest = after_value;
+	}
+	if closing && !arguments.is_empty() {
+		return None;
+	}
+	Some(ShortcodeTag {
+		range: start..end,
+		name: name.to_owned(),
+		closing,
+		self_closing,
+		arguments,
+	})
+}
@@ -0,0 +1,42 @@
+use deciduously_com_sunfish_ui as ui;
+
+#[test]
+fn styles_text_with_sgr_sequences() {
+	assert_eq!(
+		ui::ansi_to_html("\u{1b}[1;31merror\u{1b}[0m: <oops>"),
+		"<span class=\"ansi-red ansi-bold\">error</span>: &lt;oops&gt;"
+	);
+	assert_eq!(
+		ui::ansi_to_html("\\x1b[32mok\\x1b[39m done"),
+		"<span class=\"ansi-green\">ok</span> done"
+	);
+	assert_eq!(
+		ui::ansi_to_html("\u{1b}[38;2;1;2;3;1mtruecolor"),
+		"<span class=\"ansi-bold\">truecolor</span>"
+	);
+}
+
+#[test]
+fn keeps_bright_and_palette_colors_apart() {
+	assert_eq!(
+		ui::ansi_to_html("\\e[1m\\e[91merror\\e[0m \u{1b}[31mred"),
+		"<span class=\"ansi-bright-red ansi-bold\">error</span> <span class=\"ansi-red\">red</span>"
+	);
+	assert_eq!(
+		ui::ansi_to_html("\u{1b}[38;5;3;48;5;12ma\u{1b}[38;5;200mb"),
+		"<span class=\"ansi-yellow ansi-bg-bright-blue\">a</span><span class=\"ansi-yellow ansi-bg-bright-blue\">b</span>"
+	);
+	assert_eq!(
+		ui::ansi_to_html("\u{1b}[104;4mx"),
+		"<span class=\"ansi-bg-bright-blue ansi-underline\">x</span>"
+	);
+}
+
+#[test]
+fn drops_other_and_unfinished_sequences() {
+	assert_eq!(ui::ansi_to_html("\u{1b}[2Kcleared"), "cleared");
+	assert_eq!(ui::ansi_to_html("cut \u{1b}[31"), "cut ");
+	assert_eq!(ui::ansi_to_html("cut \u{1b}["), "cut ");
+	assert_eq!(ui::ansi_to_html("cut \u{1b}"), "cut \u{1b}");
+	assert_eq!(ui::ansi_to_html("\u{1b}[3é1mé"), "é");
+}
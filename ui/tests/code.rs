@@ -0,0 +1,133 @@
+use deciduously_com_sunfish_ui as ui;
+use std::borrow::Cow;
+
+#[test]
+fn wraps_snippets_like_doctests() {
+	assert_eq!(
+		ui::doctest_source("#![feature(test)]\nlet x = 1;\n"),
+		"#![allow(unused)]\n#![feature(test)]\nfn main() {\nlet x = 1;\n}\n"
+	);
+	assert_eq!(
+		ui::doctest_source("fn main() {}\n"),
+		"#![allow(unused)]\nfn main() {}\n"
+	);
+}
+
+#[test]
+fn links_snippets_to_the_playground_as_they_are_checked() {
+	let url = ui::playground_url(&ui::doctest_source("let x = 1;\n"), "2021");
+	let url = url::Url::parse(&url).unwrap();
+	let code = url
+		.query_pairs()
+		.find(|(name, _)| name == "code")
+		.map(|(_, code)| code.into_owned());
+	assert_eq!(
+		code.as_deref(),
+		Some("#![allow(unused)]\nfn main() {\nlet x = 1;\n}\n")
+	);
+	assert!(url
+		.query_pairs()
+		.any(|(name, value)| name == "edition" && value == "2021"));
+}
+
+fn render(component: impl pinwheel::prelude::Component) -> String {
+	component.into_node().to_string()
+}
+
+fn render_diff(code: &str) -> String {
+	render(ui::Code::new().code(Cow::Owned(code.to_owned())).diff(true))
+}
+
+#[test]
+fn classifies_file_headers_only_where_diffs_have_them() {
+	let html =
+		render_diff("--- a/main.sql\n+++ b/main.sql\n@@ -1,2 +1,2 @@\n--- comment\n+++x\n ctx\n");
+	assert_eq!(html.matches("code-line-hunk").count(), 3);
+	assert_eq!(html.matches("code-line-removed").count(), 1);
+	assert_eq!(html.matches("code-line-added").count(), 1);
+	assert!(html.contains("<span class=\"code-diff-marker\">-</span>-- comment"));
+	let html = render_diff("--- comment\n+ added\n");
+	assert_eq!(html.matches("code-line-hunk").count(), 0);
+	let html = render_diff(
+		"@@ -1 +1 @@\n-a\ndiff --git a/b b/b\nindex 1..2\n--- a/b\n+++ b/b\n@@ -1 +1 @@\n+b\n",
+	);
+	assert_eq!(html.matches("code-line-hunk").count(), 6);
+}
+
+#[test]
+fn scopes_highlight_themes_to_code() {
+	let css = ui::HighlightThemes::default().css();
+	assert!(css.contains(".code .keyword, .inline-code .keyword { color: #8944ab; }"));
+	assert!(css.contains(".code .function.macro, .inline-code .function.macro { color: #5e5ce6; }"));
+	assert!(css
+		.contains(".code .comment, .inline-code .comment { color: #8e8e93; font-style: italic; }"));
+	assert!(css
+		.lines()
+		.filter(|line| line.contains('{') && !line.starts_with('@'))
+		.all(|line| line.starts_with(".code ")));
+}
+
+#[test]
+fn separates_shell_commands_from_their_output() {
+	let session = "$ cargo new \\\n  hello\n     Created package\n$\n$ echo '<done>'\n<done>\n";
+	assert_eq!(
+		ui::session_commands(session).as_deref(),
+		Some("cargo new \\\n  hello\n\necho '<done>'\n")
+	);
+	assert_eq!(ui::session_commands("plain output\n$not a prompt\n"), None);
+	let html = render(
+		ui::Code::new()
+			.code(Cow::Borrowed(session))
+			.shell_session(true),
+	);
+	assert_eq!(html.matches("code-line-command").count(), 4);
+	assert_eq!(html.matches("code-line-output").count(), 2);
+	assert_eq!(
+		html.matches("<span class=\"code-prompt\">$ </span>")
+			.count(),
+		2
+	);
+	assert!(html.contains("&lt;done&gt;"));
+}
+
+#[test]
+fn hides_rustdoc_lines_from_readers_but_not_from_the_compiler() {
+	let code = "# use std::fmt;\n#\n    # let hidden = 1;\nlet x = 1;\n## not hidden\n#[derive(Debug)]\n#![no_std]\n";
+	assert_eq!(
+		ui::visible_rust_lines(code),
+		"let x = 1;\n# not hidden\n#[derive(Debug)]\n#![no_std]\n"
+	);
+	assert_eq!(
+		ui::all_rust_lines(code),
+		"use std::fmt;\n\nlet hidden = 1;\nlet x = 1;\n# not hidden\n#[derive(Debug)]\n#![no_std]\n"
+	);
+}
+
+#[test]
+fn makes_a_code_select_with_a_tab_for_each_language_with_code() {
+	let code_for_language = ui::HighlightCodeForLanguage {
+		elixir: Cow::Borrowed(""),
+		go: Cow::Borrowed(""),
+		javascript: Cow::Borrowed("let x = 1;"),
+		php: Cow::Borrowed(""),
+		python: Cow::Borrowed(""),
+		ruby: Cow::Borrowed(""),
+		rust: Cow::Borrowed("let x = 1;"),
+	};
+	let html = render(ui::CodeSelect::from(code_for_language).id("example".to_owned()));
+	assert_eq!(html.matches("class=\"code-select-label\"").count(), 2);
+	assert!(html.contains("id=\"example-1\""));
+	assert_eq!(
+		html.matches("<span class=\"keyword\">let</span>").count(),
+		2
+	);
+}
+
+#[test]
+fn recognizes_prompts_that_name_the_user_and_host() {
+	let session = "$ ssh root@example.com\nroot@example:~# ls\nfile\nroot@487693de818e:/working-dir#\nmail me at me@example.com\nroot@example# exit\n$\n";
+	assert_eq!(
+		ui::session_commands(session).as_deref(),
+		Some("ssh root@example.com\nls\n\nexit\n\n")
+	);
+}
@@ -0,0 +1,37 @@
+use deciduously_com_sunfish_ui as ui;
+
+#[test]
+fn parses_languages_flags_and_values() {
+	let info =
+		ui::FenceInfo::parse("rust,linenos,hl_lines=\"2-4 7\",title=main.rs ignore edition2018");
+	assert_eq!(info.language.as_deref(), Some("rust"));
+	assert!(info.line_numbers);
+	assert_eq!(info.highlight_lines, [2..=4, 7..=7]);
+	assert_eq!(info.title.as_deref(), Some("main.rs"));
+	assert_eq!(info.flags, ["ignore", "edition2018"]);
+	assert_eq!(info.edition(), "2018");
+	let info = ui::FenceInfo::parse("text title=\"a file, with spaces.txt\" nofold");
+	assert_eq!(info.title.as_deref(), Some("a file, with spaces.txt"));
+	assert!(info.has_flag("nofold"));
+	assert_eq!(ui::FenceInfo::parse(""), ui::FenceInfo::default());
+}
+
+#[test]
+fn parses_awkward_line_ranges() {
+	let lines = |info: &str| ui::FenceInfo::parse(info).highlight_lines;
+	assert_eq!(lines("rust hl_lines=\"5-2\""), vec![2..=5]);
+	assert_eq!(lines("rust hl_lines=\"\""), []);
+	assert_eq!(lines("rust hl_lines=3-"), []);
+	assert_eq!(lines("rust hl_lines=\"x 1;3-3\""), [1..=1, 3..=3]);
+	assert_eq!(lines("rust highlight=-1"), []);
+}
+
+#[test]
+fn looks_through_diff_prefixes_for_the_language() {
+	let info = ui::FenceInfo::parse("diff-rust");
+	assert!(info.is_diff());
+	assert_eq!(info.highlight_language(), Some(ui::Language::Rust));
+	let info = ui::FenceInfo::parse("console");
+	assert!(info.is_ansi() && info.is_shell_session());
+	assert_eq!(info.highlight_language(), Some(ui::Language::Bash));
+}
@@ -0,0 +1,395 @@
+use deciduously_com_sunfish_ui as ui;
+
+fn render(markdown: &str) -> ui::RenderedMarkdown {
+	ui::Markdown::new(markdown.to_owned()).render()
+}
+
+#[test]
+fn renders_awkward_input_without_panicking() {
+	let inputs = [
+		"",
+		"```\n```",
+		"```rust\n",
+		"    ",
+		"| a | b |\n| - | :-: |\n| 1 | 2 | 3 |\n| 4 |",
+		"# `code` in a *heading*",
+		"# ![image](/image.png) heading",
+		"> ```\n> quoted code\n> ```",
+		"- item\n  ```js\n  let x = 1;\n  ```",
+		"text[^1]\n\n[^1]: note\n\n[^1]: again",
+		"`x`{:}",
+		"`x`{:rust",
+		"<!-- /code-group -->",
+		"\u{1b}[31m ansi ```ansi",
+		"[link](<\"quoted\">)",
+		"$$a{% youtube a %}$$",
+		"$a {{< card >}} b$",
+		"$a {{< card >}}body{{< /card >}} b$ $$ {% youtube a %} $$",
+		"{% link {{< card >}} %}",
+		"{%}",
+		"{%%}",
+		"{% %}",
+		"a {%} b {%",
+		"{{<>}}",
+		"{{< >}}",
+		"{{< card",
+		"$$",
+		"$$ a",
+		"a $$\n\nb",
+	];
+	for input in inputs {
+		for html_policy in [ui::HtmlPolicy::Trusted, ui::HtmlPolicy::default()] {
+			let _ = ui::Markdown::new(input.to_owned())
+				.html_policy(html_policy)
+				.render();
+		}
+	}
+}
+
+#[test]
+fn warns_with_the_line_of_the_problem() {
+	let rendered = render("text\n\n<!-- /code-group -->\n");
+	assert_eq!(rendered.warnings.len(), 1);
+	assert_eq!(rendered.warnings[0].line, 3);
+}
+
+#[test]
+fn warns_about_unclosed_code_groups_and_still_renders_them() {
+	let rendered = render("<!-- code-group -->\n\n```js\nlet x = 1;\n```\n");
+	assert_eq!(rendered.warnings.len(), 1);
+	assert!(rendered.html.contains("code-group-0"));
+}
+
+#[test]
+fn renders_code_groups_with_copy_buttons_and_ends_them_at_text() {
+	let rendered = render(
+		"<!-- code-group -->\n\n```js\nlet x = 1;\n```\n\n```rust,playground\nlet x = 1;\n```\n\n<!-- /code-group -->\n",
+	);
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("id=\"code-copy-0\""));
+	assert!(rendered.html.contains("id=\"code-copy-1\""));
+	assert!(rendered.html.contains("code-header-playground"));
+	let rendered = render(
+		"<!-- code-group -->\n\n```js\nlet x = 1;\n```\n\nBetween.\n\n```rust\nlet x = 1;\n```\n\n<!-- /code-group -->\n",
+	);
+	assert_eq!(rendered.warnings.len(), 2);
+	assert_eq!(rendered.warnings[0].line, 7);
+	let group = rendered.html.find("code-group-0").unwrap();
+	let text = rendered.html.find("<p>Between.</p>").unwrap();
+	assert!(group < text);
+}
+
+#[test]
+fn warns_about_undefined_footnotes() {
+	let rendered = render("text[^missing]\n");
+	assert_eq!(rendered.warnings.len(), 1);
+	assert_eq!(rendered.warnings[0].line, 1);
+}
+
+#[test]
+fn renders_footnotes() {
+	let rendered = render("text[^note]\n\n[^note]: the note\n");
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("href=\"#footnote-note\""));
+	assert!(rendered.html.contains("id=\"footnote-note\""));
+}
+
+#[test]
+fn renders_headings_with_inline_markup() {
+	let rendered = render("## The `main` *function*\n");
+	assert!(rendered
+		.html
+		.starts_with("<h2 id=\"the_main_function\">The "));
+	assert!(rendered.html.ends_with("<em>function</em></h2>"));
+}
+
+#[test]
+fn renders_image_alt_text_and_captions() {
+	let rendered = render("![a *small* `cat`](/cat.png)\n");
+	assert!(rendered.html.contains("alt=\"a small cat\""));
+	assert!(!rendered.html.contains("<em>"));
+	assert!(!rendered.html.contains("figure"));
+	let rendered = render("![a cat](/cat.png \"The cat, asleep\")\n");
+	assert!(rendered.html.contains("alt=\"a cat\""));
+	assert!(rendered
+		.html
+		.contains("<figcaption class=\"image-figure-caption\">The cat, asleep</figcaption>"));
+	let rendered = render("![a ![b](b.png)](a.png)\n");
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("alt=\"a b\""));
+	assert!(rendered.html.contains("src=\"a.png\""));
+	assert!(!rendered.html.contains("b.png"));
+}
+
+#[test]
+fn sanitizes_raw_html_by_default() {
+	let rendered = render(
+		"<div onclick=\"steal()\" title='hi'>\n<script>\nalert(1)\n</script>\n</div>\n\n[x](javascript:alert(1)) <a href=\"&#106;avascript:alert(1)\">y</a> <iframe src=\"/\"></iframe>\n",
+	);
+	assert!(rendered.html.contains("<div title=\"hi\">"));
+	assert!(rendered.html.contains("</div>"));
+	for removed in ["onclick", "script", "alert(1)\n", "javascript", "iframe"] {
+		assert!(!rendered.html.contains(removed), "{removed}");
+	}
+	assert_eq!(rendered.warnings.len(), 5);
+}
+
+#[test]
+fn renders_raw_html_as_written_when_trusted() {
+	let markdown = "<div onclick=\"go()\">\n<script>alert(1)</script>\n</div>\n";
+	let rendered = ui::Markdown::new(markdown.to_owned())
+		.html_policy(ui::HtmlPolicy::Trusted)
+		.render();
+	assert!(rendered.html.contains("<div onclick=\"go()\">"));
+	assert!(rendered.html.contains("<script>alert(1)</script>"));
+	assert_eq!(rendered.warnings, []);
+}
+
+#[test]
+fn renders_liquid_tags_outside_code() {
+	let rendered = render(
+		"{# {% youtube X5KmB8Laemg %} #}\n\nSee {% link someone/a-post--title-4d3e %}.\n\n```html\n{% block content %}{% endblock %}\n```\n",
+	);
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered
+		.html
+		.contains("https://www.youtube-nocookie.com/embed/X5KmB8Laemg"));
+	assert!(rendered.html.contains(
+		"<a href=\"https://dev.to/someone/a-post--title-4d3e\" class=\"link\">A post title</a>"
+	));
+	assert!(rendered.html.contains("{% block content %}"));
+	assert!(!rendered.html.contains("{#"));
+}
+
+#[test]
+fn warns_about_unsupported_liquid_tags() {
+	let rendered = render("text\n\n{% github deciduously/repo %}\n");
+	assert_eq!(rendered.warnings.len(), 1);
+	assert_eq!(rendered.warnings[0].line, 3);
+}
+
+#[test]
+fn renders_shortcodes_with_markdown_bodies() {
+	let rendered = render(
+		"{{< card title=\"Notes\" >}}\nSome *markdown* and {{< link href=\"/a\" >}}a link{{< /link >}}.\n\n```rust\nlet a = 1;\n```\n{{< /card >}}\n\n```rust\nlet b = 2;\n```\n",
+	);
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered
+		.html
+		.contains("<div class=\"card\"><div class=\"card-title\">Notes</div>"));
+	assert!(rendered.html.contains("<em>markdown</em>"));
+	assert!(rendered.html.contains(
+		"<a href=\"/a\" class=\"link\"><span class=\"shortcode-body\">a link</span></a>"
+	));
+	assert!(rendered.html.contains("code-copy-0"));
+	assert!(rendered.html.contains("code-copy-1"));
+}
+
+#[test]
+fn renders_inline_shortcodes_at_the_start_of_a_line() {
+	let rendered = render("{{< link href=\"/a\" text=\"A\" >}} starts this line.\n");
+	assert_eq!(rendered.warnings, []);
+	assert_eq!(
+		rendered.html,
+		"<p><a href=\"/a\" class=\"link\">A</a> starts this line.</p>"
+	);
+}
+
+#[test]
+fn leaves_shortcodes_in_code_as_written() {
+	let rendered = render("`{{< card >}}`\n\n```\n{{< link href=\"/a\" />}}\n```\n");
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("{{&lt; card &gt;}}"));
+	assert!(rendered
+		.html
+		.contains("{{&lt; link href=&quot;/a&quot; /&gt;}}"));
+}
+
+#[test]
+fn warns_about_unknown_shortcodes_and_arguments() {
+	let rendered = render("{{< img src=\"/a.png\" size=\"big\" />}}\n\n{{< chart >}}\n");
+	assert_eq!(rendered.warnings.len(), 2);
+	assert_eq!(rendered.warnings[0].line, 1);
+	assert_eq!(rendered.warnings[1].line, 3);
+}
+
+#[test]
+fn renders_registered_shortcodes() {
+	use pinwheel::prelude::*;
+	let shortcodes = ui::Shortcodes::empty().register("kbd", |arguments, _| {
+		Ok(kbd()
+			.child(arguments.required::<String>("key")?)
+			.into_node())
+	});
+	let rendered = ui::Markdown::new("Press {{< kbd key=\"q\" />}} to quit.".to_owned())
+		.shortcodes(shortcodes)
+		.render();
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("Press <kbd>q</kbd> to quit."));
+}
+
+#[test]
+fn renders_callouts_from_marked_blockquotes() {
+	let rendered = render(
+		"> [!WARNING]\n> Mind the *gap*.\n\n> [!NOTE] is not on its own line\n\n> Just a quote.\n",
+	);
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("class=\"callout callout-warning\""));
+	assert!(rendered
+		.html
+		.contains("<div class=\"callout-body\"><p>Mind the <em>gap</em>.</p></div>"));
+	assert!(!rendered.html.contains("[!WARNING]"));
+	assert!(rendered
+		.html
+		.contains("<blockquote><p>[!NOTE] is not on its own line</p></blockquote>"));
+	assert!(rendered
+		.html
+		.contains("<blockquote><p>Just a quote.</p></blockquote>"));
+}
+
+#[test]
+fn renders_videos_without_loading_third_party_players() {
+	let rendered = render("{% youtube X5KmB8Laemg %}\n\n![A demo of the game](demo.mp4)\n");
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("id=\"video-embed-0\""));
+	assert!(!rendered.html.contains("<iframe"));
+	assert!(!rendered.html.contains("ytimg.com"));
+	assert!(rendered.html.contains("src=\"/youtube/X5KmB8Laemg.jpg\""));
+	assert!(rendered
+		.html
+		.contains("href=\"https://www.youtube-nocookie.com/embed/X5KmB8Laemg\""));
+	assert!(rendered.html.contains(
+		"<video aria-label=\"A demo of the game\" controls preload=\"metadata\" src=\"demo.mp4\""
+	));
+}
+
+#[test]
+fn renders_math_to_mathml() {
+	let rendered =
+		render("Euler wrote $e^{i\\pi} + 1 = 0$ for $5 or $10.\n\n$$\n\\frac{a}{b}\n$$\n\n`$x$`\n");
+	assert_eq!(rendered.warnings, []);
+	assert!(rendered.html.contains("<math display=\"inline\">"));
+	assert!(rendered.html.contains("<math display=\"block\">"));
+	assert!(rendered.html.contains("for $5 or $10."));
+	assert!(rendered
+		.html
+		.contains("<span class=\"inline-code\">$x$</span>"));
+}
+
+#[test]
+fn warns_about_invalid_math() {
+	let rendered = render("Some text.\n\nA fraction $\\frac{1}$ missing its denominator.\n");
+	assert_eq!(rendered.warnings.len(), 1);
+	assert_eq!(rendered.warnings[0].line, 3);
+	assert!(rendered.html.contains("<merror"));
+}
+
+#[test]
+fn typesets_text_but_not_code() {
+	let markdown = "\"Done\" -- at 10:30:45 :tada: `\"a\" -- :tada:`\n";
+	let rendered = render(markdown);
+	assert_eq!(
+		rendered.html,
+		"<p>“Done” – at 10:30:45 :tada: <span class=\"inline-code\">&quot;a&quot; -- :tada:</span></p>"
+	);
+	let rendered = ui::Markdown::new(markdown.to_owned())
+		.typography(ui::Typography {
+			smart_punctuation: false,
+			emoji_shortcodes: true,
+		})
+		.render();
+	assert_eq!(
+		rendered.html,
+		"<p>&quot;Done&quot; -- at 10:30:45 🎉 <span class=\"inline-code\">&quot;a&quot; -- :tada:</span></p>"
+	);
+}
+
+#[test]
+fn excerpts_end_at_the_more_marker_or_the_first_paragraph() {
+	let markdown = ui::Markdown::new(
+		"{% youtube X5KmB8Laemg %}\n\nThe *first* paragraph,\nwith `code`.\n\nThe second.\n"
+			.to_owned(),
+	);
+	let excerpt = markdown.excerpt();
+	assert_eq!(excerpt.plain_text(), "The first paragraph, with code.");
+	let markdown = ui::Markdown::new(
+		"# Intro\n\nOne.\n\n```\nlet a = 1;\n```\n\n<!-- more -->\n\nTwo.\n".to_owned(),
+	);
+	let excerpt = markdown.excerpt();
+	assert_eq!(excerpt.plain_text(), "Intro One.");
+	assert!(!markdown.render().html.contains("more"));
+}
+
+#[test]
+fn excerpts_keep_the_definitions_they_refer_to() {
+	let markdown = ui::Markdown::new(
+		"See [the docs][docs] and a note.[^note]\n\nMore.[^other]\n\n[docs]: https://example.com\n\n[^note]: The note.\n\n[^other]: Another.\n"
+			.to_owned(),
+	);
+	let html = markdown.excerpt().render().html;
+	assert!(html.contains("<a href=\"https://example.com\">the docs</a>"));
+	assert!(html.contains("The note."));
+	assert!(!html.contains("Another."));
+	assert!(!html.contains("More."));
+}
+
+#[test]
+fn numbers_code_blocks_across_documents_rendered_together() {
+	let document = ui::Markdown::new("```\na\n```\n".to_owned());
+	let rendered = ui::Markdown::render_all(&[document.clone(), document]);
+	assert!(rendered[0].html.contains("id=\"code-copy-0\""));
+	assert!(rendered[1].html.contains("id=\"code-copy-1\""));
+}
+
+#[test]
+fn folds_long_code_blocks_and_renders_details() {
+	let long = "```\na\nb\nc\n```\n\n```text nofold\na\nb\nc\n```\n\n```\na\nb\n```\n";
+	let markdown = ui::Markdown::new(long.to_owned()).code_fold_lines(2);
+	let html = markdown.render().html;
+	assert_eq!(html.matches("class=\"code-fold\"").count(), 1);
+	assert!(html.contains("id=\"code-fold-0\""));
+	assert!(html.contains("Show all 3 lines"));
+	assert!(!ui::Markdown::new(long.to_owned())
+		.render()
+		.html
+		.contains("code-fold"));
+	let markdown = "{{< details summary=\"Spoiler\" >}}It was *him*.{{< /details >}}\n";
+	let html = ui::Markdown::new(markdown.to_owned()).render().html;
+	assert!(html.starts_with("<details class=\"details\"><summary class=\"details-summary\">Spoiler</summary><div class=\"details-body\">"));
+	assert!(html.contains("<em>him</em>"));
+	assert!(!html.contains("open"));
+}
+
+#[test]
+fn links_rust_paths_to_their_documentation() {
+	let markdown = "[`Vec`](std::vec::Vec), [`tokio::spawn`], [`println!`](std::println!), [`Read`](trait@std::io::Read), [`u8`](primitive@u8), [`serde`](mod@serde) and [not a path].";
+	let html = ui::Markdown::new(markdown.to_owned()).render().html;
+	assert_eq!(
+		html,
+		"<p><a href=\"https://doc.rust-lang.org/std/?search=std::vec::Vec&amp;go_to_first=true\"><span class=\"inline-code\">Vec</span></a>, \
+		<a href=\"https://docs.rs/tokio/latest/tokio/?search=tokio::spawn&amp;go_to_first=true\"><span class=\"inline-code\">tokio::spawn</span></a>, \
+		<a href=\"https://doc.rust-lang.org/std/macro.println.html\"><span class=\"inline-code\">println!</span></a>, \
+		<a href=\"https://doc.rust-lang.org/std/io/trait.Read.html\"><span class=\"inline-code\">Read</span></a>, \
+		<a href=\"https://doc.rust-lang.org/std/primitive.u8.html\"><span class=\"inline-code\">u8</span></a>, \
+		<a href=\"https://docs.rs/serde/latest/serde/\"><span class=\"inline-code\">serde</span></a> \
+		and [not a path].</p>"
+	);
+}
+
+#[test]
+fn highlights_inline_code_with_a_language_hint() {
+	let rendered = render(
+		"`Vec<T>`{:rust}, `let x = 1;`{:rust} and `x`{:nope}. Plain `y`{: z} and `{:rust}`.\n",
+	);
+	assert_eq!(
+		rendered.html,
+		"<p><span class=\"inline-code\"><span class=\"constructor\">Vec</span>&lt;<span class=\"constructor\">T</span>&gt;</span>, \
+		<span class=\"inline-code\"><span class=\"keyword\">let</span> x = <span class=\"constant builtin\">1</span><span class=\"punctuation delimiter\">;</span></span> \
+		and <span class=\"inline-code\">x</span>. Plain <span class=\"inline-code\">y</span>{: z} and <span class=\"inline-code\">{:rust}</span>.</p>"
+	);
+	assert_eq!(rendered.warnings.len(), 1);
+	assert_eq!(
+		rendered.warnings[0].message,
+		"no grammar to highlight inline nope"
+	);
+}